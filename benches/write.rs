@@ -0,0 +1,31 @@
+use std::{env, fs::File, io::Write, str::FromStr, time::Instant};
+
+use pngme::{chunk::Chunk, chunk_type::ChunkType, png::Png, Result};
+
+const CHUNK_COUNT: usize = 2000;
+
+fn main() -> Result<()> {
+    let chunks = (0..CHUNK_COUNT)
+        .map(|i| {
+            let data = format!("chunk number {}", i).into_bytes();
+            Ok(Chunk::new(ChunkType::from_str("ruSt")?, data))
+        })
+        .collect::<Result<_>>()?;
+    let png = Png::from_chunks(chunks);
+    let path = env::temp_dir().join("pngme_bench_write.png");
+
+    let start = Instant::now();
+    let mut file = File::create(&path)?;
+    file.write_all(png.header())?;
+    for chunk in png.chunks() {
+        chunk.write_to(&mut file)?;
+    }
+    println!("unbuffered: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    png.write_to(File::create(&path)?)?;
+    println!("buffered:   {:?}", start.elapsed());
+
+    std::fs::remove_file(path)?;
+    Ok(())
+}
@@ -1,9 +1,23 @@
 use clap::{Parser, Subcommand};
 
+pub const DEFAULT_CHUNK_TYPE: &str = "ruSt";
+
 #[derive(Parser)]
 pub struct Cli {
     #[clap(subcommand)]
     pub command: Command,
+    /// Suppress warnings on stderr
+    #[clap(long, short, global = true)]
+    pub quiet: bool,
+    /// Recompute every chunk's CRC when writing a file
+    #[clap(long, global = true)]
+    pub recompute_crc: bool,
+    /// Print how long parsing, editing, CRC recomputation and writing took to stderr
+    #[clap(long, global = true)]
+    pub timings: bool,
+    /// Make encode and remove write to <name>.pngme.<ext> instead of overwriting the input
+    #[clap(long, global = true)]
+    pub no_inplace: bool,
 }
 
 #[derive(Subcommand)]
@@ -12,29 +26,259 @@ pub enum Command {
     Decode(DecodeArgs),
     Remove(RemoveArgs),
     Print(PrintArgs),
+    Normalize(NormalizeArgs),
+    Minify(MinifyArgs),
+    Scan(ScanArgs),
+    ExtractChunk(ExtractChunkArgs),
+    InjectChunk(InjectChunkArgs),
+    Interactive(InteractiveArgs),
+    Stats(StatsArgs),
+    Verify(VerifyArgs),
+    Metadata(MetadataArgs),
+    Diff(DiffArgs),
+    Grep(GrepArgs),
+    Fix(FixArgs),
+    Layout(LayoutArgs),
+    Merge(MergeArgs),
+    DumpText(DumpTextArgs),
+    Dedup(DedupArgs),
+    Checksum(ChecksumArgs),
+    Explode(ExplodeArgs),
+    Implode(ImplodeArgs),
 }
 
 #[derive(Parser)]
 pub struct EncodeArgs {
     pub file_path: String,
-    pub chunk_type: String,
-    pub message: String,
+    /// Chunk type to write [default: ruSt]; with a single argument it is taken as the message
+    pub chunk_type: Option<String>,
+    pub message: Option<String>,
     pub output_file: Option<String>,
+    /// Read the chunk data from a file instead of the message argument
+    #[clap(long, conflicts_with = "message")]
+    pub file: Option<String>,
+    /// Chunk data as a hex string, e.g. deadbeef
+    #[clap(long, conflicts_with_all = &["message", "file"])]
+    pub hex: Option<String>,
+    /// Replace the data of an existing chunk instead of appending a new one
+    #[clap(long)]
+    pub replace_data: bool,
+    /// Do nothing if a chunk of this type already exists
+    #[clap(long, conflicts_with = "replace-data")]
+    pub if_absent: bool,
+    /// Check that IHDR, PLTE and IDAT are byte-identical after encoding
+    #[clap(long)]
+    pub verify_image_unchanged: bool,
+    /// Encode even into a critical chunk type, a file with corrupt chunks (kept as they are),
+    /// or when a backup already exists
+    #[clap(long)]
+    pub force: bool,
+    /// Copy the original to <file>.bak before writing in place
+    #[clap(long)]
+    pub backup: bool,
+    /// Store a 4-byte big-endian length before the message
+    #[clap(long)]
+    pub length_prefixed: bool,
+    /// XOR the data with a repeating key; obfuscation only, not encryption
+    #[clap(long)]
+    pub xor: Option<String>,
+    /// Write several type=message pairs in one pass
+    #[clap(
+        long,
+        multiple_values = true,
+        conflicts_with_all = &["chunk-type", "message", "file", "hex"]
+    )]
+    pub multi: Vec<String>,
 }
 
 #[derive(Parser)]
 pub struct DecodeArgs {
     pub file_path: String,
+    #[clap(default_value = DEFAULT_CHUNK_TYPE)]
     pub chunk_type: String,
+    /// Use the Nth image of a file containing concatenated PNGs
+    #[clap(long)]
+    pub index: Option<usize>,
+    /// Replace invalid UTF-8 with U+FFFD instead of failing
+    #[clap(long)]
+    pub lossy: bool,
+    /// Read a message written with encode --length-prefixed, ignoring trailing bytes
+    #[clap(long)]
+    pub length_prefixed: bool,
+    /// Undo encode --xor with the same key
+    #[clap(long)]
+    pub xor: Option<String>,
+    /// Text encoding of the chunk data
+    #[clap(long, default_value = "utf8", possible_values = &["utf8", "latin1"])]
+    pub encoding: String,
+    /// How to render the payload; raw writes the bytes to stdout unchanged
+    #[clap(long, default_value = "text", possible_values = &["text", "hex", "base64", "raw"])]
+    pub format: String,
+    /// Print the message without a trailing newline
+    #[clap(long)]
+    pub no_newline: bool,
 }
 
 #[derive(Parser)]
 pub struct RemoveArgs {
     pub file_path: String,
     pub chunk_type: String,
+    /// Copy the original to <file>.bak before writing
+    #[clap(long)]
+    pub backup: bool,
+    /// Overwrite an existing backup
+    #[clap(long)]
+    pub force: bool,
+    /// Zero the original file before rewriting it and make sure the removed data is gone
+    #[clap(long, conflicts_with = "backup")]
+    pub shred: bool,
 }
 
 #[derive(Parser)]
 pub struct PrintArgs {
     pub file_path: String,
+    /// Show chunk lengths as raw byte counts
+    #[clap(long)]
+    pub bytes: bool,
+    /// Show non-UTF-8 data as text with U+FFFD replacement characters
+    #[clap(long)]
+    pub lossy: bool,
+    /// Show data as text with non-printable bytes escaped as \xNN
+    #[clap(long, conflicts_with = "lossy")]
+    pub escape: bool,
+    /// Use the Nth image of a file containing concatenated PNGs
+    #[clap(long)]
+    pub index: Option<usize>,
+    /// Fail instead of showing what could be parsed from a truncated or damaged file
+    #[clap(long)]
+    pub strict: bool,
+    /// Highlight chunks by kind and bad CRCs in red
+    #[clap(long, default_value = "auto", possible_values = &["auto", "always", "never"])]
+    pub color: String,
+    /// Look for the PNG signature in the first 1 KiB instead of requiring it at offset 0
+    #[clap(long)]
+    pub scan_signature: bool,
+}
+
+#[derive(Parser)]
+pub struct NormalizeArgs {
+    pub file_path: String,
+}
+
+#[derive(Parser)]
+pub struct MinifyArgs {
+    pub file_path: String,
+    pub output_file: Option<String>,
+    /// Concatenate all IDAT chunks into a single IDAT chunk
+    #[clap(long)]
+    pub merge_idat: bool,
+}
+
+#[derive(Parser)]
+pub struct ScanArgs {
+    pub file_path: String,
+}
+
+#[derive(Parser)]
+pub struct ExtractChunkArgs {
+    pub file_path: String,
+    pub chunk_type: String,
+}
+
+#[derive(Parser)]
+pub struct InjectChunkArgs {
+    pub file_path: String,
+}
+
+#[derive(Parser)]
+pub struct InteractiveArgs {
+    pub file_path: String,
+}
+
+#[derive(Parser)]
+pub struct StatsArgs {
+    pub file_path: String,
+}
+
+#[derive(Parser)]
+pub struct VerifyArgs {
+    pub file_path: String,
+    /// Also check that every chunk is in a position the spec allows
+    #[clap(long)]
+    pub ordering: bool,
+}
+
+#[derive(Parser)]
+pub struct MetadataArgs {
+    pub file_path: String,
+}
+
+#[derive(Parser)]
+pub struct DiffArgs {
+    pub file_path: String,
+    pub other_file_path: String,
+}
+
+#[derive(Parser)]
+pub struct GrepArgs {
+    pub file_path: String,
+    pub pattern: String,
+    /// Match ASCII letters case-insensitively
+    #[clap(long, short)]
+    pub ignore_case: bool,
+}
+
+#[derive(Parser)]
+pub struct FixArgs {
+    pub file_path: String,
+    pub output_file: Option<String>,
+}
+
+#[derive(Parser)]
+pub struct LayoutArgs {
+    pub file_path: String,
+}
+
+#[derive(Parser)]
+pub struct MergeArgs {
+    pub file_path: String,
+    /// File to copy ancillary chunks from
+    pub other_file_path: String,
+    pub output_file: Option<String>,
+    /// Don't copy chunks whose type is already present
+    #[clap(long)]
+    pub skip_existing: bool,
+}
+
+#[derive(Parser)]
+pub struct DumpTextArgs {
+    pub file_path: String,
+    pub output_file: String,
+}
+
+#[derive(Parser)]
+pub struct DedupArgs {
+    pub file_path: String,
+}
+
+#[derive(Parser)]
+pub struct ChecksumArgs {
+    pub file_path: String,
+    /// Take the CRC of the chunk CRCs in order instead of hashing the whole file with SHA-256
+    #[clap(long)]
+    pub crc_only: bool,
+}
+
+#[derive(Parser)]
+pub struct ExplodeArgs {
+    pub file_path: String,
+    /// Directory to write one NN_TYPE.chunk file per chunk into
+    pub dir: String,
+}
+
+#[derive(Parser)]
+pub struct ImplodeArgs {
+    /// Directory of .chunk files written by explode
+    pub dir: String,
+    pub output_file: String,
 }
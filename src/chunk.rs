@@ -1,13 +1,18 @@
 use std::{
     convert::TryFrom,
     fmt::{Display, Formatter, Result as FmtResult},
-    io::{BufRead, BufReader, Read},
+    io::{self, BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write},
+    str::FromStr,
 };
 
-use crc::{Crc, CRC_32_ISO_HDLC};
+use crc::Digest;
 
-use crate::{chunk_type::ChunkType, Error, Result};
+use crate::{chunk_type::ChunkType, png_crc, png_crc_digest, Error, Result};
 
+/// `length` and `crc` are cached from `data`: `length as usize == data.len()`
+/// and `crc == crc_checksum(chunk_type, data)`. Anything that mutates `data`
+/// must refresh both.
+#[derive(Clone, PartialEq, Eq)]
 pub struct Chunk {
     length: u32,
     chunk_type: ChunkType,
@@ -22,6 +27,22 @@ impl Chunk {
         Self::build(data.len() as u32, chunk_type, data, crc)
     }
 
+    pub fn from_strings(chunk_type: &str, message: &str) -> Result<Self> {
+        Ok(Self::new(
+            ChunkType::from_str(chunk_type)?,
+            message.as_bytes().to_vec(),
+        ))
+    }
+
+    // IEND 没有数据，CRC 固定为 0xAE426082
+    pub fn iend() -> Self {
+        Self::new(ChunkType::IEND, vec![])
+    }
+
+    pub fn is_iend(&self) -> bool {
+        self.chunk_type == ChunkType::IEND
+    }
+
     pub fn length(&self) -> u32 {
         self.length
     }
@@ -38,6 +59,51 @@ impl Chunk {
         self.crc
     }
 
+    pub fn set_data(&mut self, data: Vec<u8>) {
+        self.length = data.len() as u32;
+        self.crc = Self::crc_checksum(&self.chunk_type, &data);
+        self.data = data;
+        self.verify_invariants();
+    }
+
+    pub fn verify_invariants(&self) {
+        debug_assert_eq!(self.length as usize, self.data.len(), "stale chunk length");
+        debug_assert_eq!(
+            self.crc,
+            Self::crc_checksum(&self.chunk_type, &self.data),
+            "stale chunk crc"
+        );
+    }
+
+    // 按当前数据重新计算 length 和 CRC，返回是否有变化
+    pub fn recompute_crc(&mut self) -> bool {
+        let length = self.data.len() as u32;
+        let crc = Self::crc_checksum(&self.chunk_type, &self.data);
+        let changed = (length, crc) != (self.length, self.crc);
+
+        self.length = length;
+        self.crc = crc;
+        changed
+    }
+
+    // 缓存的 CRC 是否和当前数据一致
+    pub fn has_valid_crc(&self) -> bool {
+        self.crc == Self::crc_checksum(&self.chunk_type, &self.data)
+    }
+
+    // CRC-32 太弱，去重和完整性校验用 SHA-256
+    #[cfg(feature = "sha2")]
+    pub fn payload_sha256(&self) -> [u8; 32] {
+        use sha2::Digest;
+
+        sha2::Sha256::digest(&self.data).into()
+    }
+
+    // 只比较类型和数据，不管缓存的 length/crc 是否过期
+    pub fn content_eq(&self, other: &Chunk) -> bool {
+        self.chunk_type == other.chunk_type && self.data == other.data
+    }
+
     // 妙
     pub fn data_as_string(&self) -> Result<String> {
         // .map_err() 把 FromUtf8Error 进行处理
@@ -45,6 +111,22 @@ impl Chunk {
         String::from_utf8(self.data.clone()).map_err(Into::into)
     }
 
+    // 先看是不是合法 UTF-8，再看按 Latin-1 解读是否全是可打印字符
+    pub fn detect_encoding(&self) -> Option<&'static str> {
+        if std::str::from_utf8(&self.data).is_ok() {
+            return Some("utf8");
+        }
+
+        let printable = |b: &u8| matches!(b, b'\t' | b'\n' | b'\r' | 0x20..=0x7e | 0xa0..=0xff);
+        self.data.iter().all(printable).then_some("latin1")
+    }
+
+    pub(crate) fn data_repr(&self) -> String {
+        self.data_as_string()
+            .map(|s| format!("b\"{}\"", s))
+            .unwrap_or(format!("{:?}", self.data))
+    }
+
     // 妙
     pub fn as_bytes(&self) -> Vec<u8> {
         self.length
@@ -74,34 +156,129 @@ impl Chunk {
         // 换句话说，它将两个迭代器链接在一起，形成一个链。
     }
 
+    pub fn as_bytes_verified(&self) -> Result<Vec<u8>> {
+        if self.crc != Self::crc_checksum(&self.chunk_type, &self.data) {
+            return Err("stale chunk crc".into());
+        }
+
+        Ok(self.as_bytes())
+    }
+
+    // 逐段写出，不拼接整块字节；调用方负责缓冲
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&self.length.to_be_bytes())?;
+        writer.write_all(&self.chunk_type.bytes())?;
+        writer.write_all(&self.data)?;
+        writer.write_all(&self.crc.to_be_bytes())?;
+
+        Ok(())
+    }
+
     // 妙
     // 这个方法的实现确实妙，用 BufReader 按顺序读
-    pub fn read_chunk(reader: &mut BufReader<&[u8]>) -> Result<Chunk> {
+    pub fn read_chunk<R: Read>(reader: &mut R) -> Result<Chunk> {
+        Self::read_chunk_limited(reader, Self::MAX_LENGTH)
+    }
+
+    pub fn read_at<R: Read + Seek>(reader: &mut R, offset: u64) -> Result<Chunk> {
+        reader.seek(SeekFrom::Start(offset))?;
+        Self::read_chunk(reader)
+    }
+
+    pub(crate) fn read_chunk_limited<R: Read>(reader: &mut R, max_length: usize) -> Result<Chunk> {
+        let chunk = Self::read_chunk_unverified(reader, max_length)?;
+        Self::check_crc(&chunk.chunk_type, &chunk.data, chunk.crc)?;
+
+        Ok(chunk)
+    }
+
+    // 保留文件里声明的 CRC，哪怕它是错的，留给 fix 之类的修复流程
+    pub(crate) fn read_chunk_unverified<R: Read>(
+        reader: &mut R,
+        max_length: usize,
+    ) -> Result<Chunk> {
         let mut buffer = [0; 4];
 
         reader.read_exact(&mut buffer)?;
         let length = u32::from_be_bytes(buffer);
+        let data_length = Self::checked_length(length, max_length)?;
 
         reader.read_exact(&mut buffer)?;
         let chunk_type = buffer.try_into()?;
 
-        let mut data = vec![0; length as usize];
-        reader.read_exact(&mut data)?;
+        // 按实际读到的字节增长，不按声明的长度一次性分配
+        let mut data = vec![];
+        reader
+            .by_ref()
+            .take(data_length as u64)
+            .read_to_end(&mut data)?;
+        if data.len() != data_length {
+            return Err(io::Error::from(ErrorKind::UnexpectedEof).into());
+        }
 
         reader.read_exact(&mut buffer)?;
         let crc = u32::from_be_bytes(buffer);
 
-        if crc != Self::crc_checksum(&chunk_type, &data) {
-            return Err("invalid chunk".into());
-        }
-
         Ok(Self::build(length, chunk_type, data, crc))
     }
 }
 
 // 类似类方法
 impl Chunk {
-    const CRC_32: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+    // 规范规定长度不超过 2^31 - 1
+    pub const MAX_LENGTH: usize = (1 << 31) - 1;
+
+    // 32 位平台上 u32 转 usize 也不会溢出，但仍要先检查再分配
+    fn checked_length(length: u32, max_length: usize) -> Result<usize> {
+        usize::try_from(length)
+            .ok()
+            .filter(|length| *length <= max_length)
+            .ok_or_else(|| {
+                format!("chunk length {} exceeds limit of {}", length, max_length).into()
+            })
+    }
+
+    // 返回 (声明的 CRC, 实际算出的 CRC, 是否一致)，CRC 不对不算错误
+    pub fn crc_status(bytes: &[u8]) -> Result<(u32, u32, bool)> {
+        let length = u32::from_be_bytes(bytes.get(..4).ok_or("invalid chunk")?.try_into()?);
+        let data_end = Self::checked_length(length, Self::MAX_LENGTH)?
+            .checked_add(8)
+            .ok_or("invalid chunk")?;
+        let covered = bytes.get(4..data_end).ok_or("invalid chunk")?;
+        let declared = bytes
+            .get(data_end..data_end + 4)
+            .ok_or("invalid chunk")?
+            .try_into()?;
+
+        let declared = u32::from_be_bytes(declared);
+        let computed = png_crc(covered);
+        Ok((declared, computed, declared == computed))
+    }
+
+    // 不构造 Chunk，只看长度字段、类型和 CRC，适合先快速过滤一遍
+    pub fn is_valid_bytes(bytes: &[u8]) -> bool {
+        let Some(length) = bytes.get(..4) else {
+            return false;
+        };
+        let length = u32::from_be_bytes([length[0], length[1], length[2], length[3]]);
+
+        bytes.len().checked_sub(12) == Some(length as usize)
+            && bytes[4..8].iter().all(u8::is_ascii_alphabetic)
+            && matches!(Self::crc_status(bytes), Ok((_, _, true)))
+    }
+
+    fn check_crc(chunk_type: &ChunkType, data: &[u8], declared: u32) -> Result<()> {
+        let computed = Self::crc_checksum(chunk_type, data);
+        if declared != computed {
+            return Err(format!(
+                "crc mismatch in {} chunk: declared {:08x}, computed {:08x}",
+                chunk_type, declared, computed
+            )
+            .into());
+        }
+
+        Ok(())
+    }
 
     fn crc_checksum(chunk_type: &ChunkType, data: &[u8]) -> u32 {
         let bytes: Vec<_> = chunk_type
@@ -111,7 +288,7 @@ impl Chunk {
             .copied()
             .collect();
 
-        Self::CRC_32.checksum(&bytes)
+        png_crc(&bytes)
     }
 
     fn build(length: u32, chunk_type: ChunkType, data: Vec<u8>, crc: u32) -> Self {
@@ -139,17 +316,199 @@ impl TryFrom<&[u8]> for Chunk {
     }
 }
 
+// 序列化成类型加 base64 数据，length 和 crc 反序列化时重新计算
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerdeChunk {
+    chunk_type: ChunkType,
+    data: String,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Chunk {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        use base64::Engine;
+
+        SerdeChunk {
+            chunk_type: self.chunk_type.clone(),
+            data: base64::engine::general_purpose::STANDARD.encode(&self.data),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Chunk {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        use base64::Engine;
+
+        let chunk = SerdeChunk::deserialize(deserializer)?;
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(chunk.data)
+            .map_err(serde::de::Error::custom)?;
+
+        Ok(Chunk::new(chunk.chunk_type, data))
+    }
+}
+
+// 复用 vec 的内存，只把数据挪到开头
+impl TryFrom<Vec<u8>> for Chunk {
+    type Error = Error;
+
+    fn try_from(mut value: Vec<u8>) -> Result<Self> {
+        let mut rest = value.as_slice();
+        let chunk = ChunkRef::read(&mut rest)?;
+        if !rest.is_empty() {
+            return Err("invalid chunk".into());
+        }
+
+        let (length, chunk_type, crc) = (chunk.length(), chunk.chunk_type().clone(), chunk.crc());
+        value.truncate(8 + length as usize);
+        value.drain(..8);
+
+        Ok(Self::build(length, chunk_type, value, crc))
+    }
+}
+
+// 和 Chunk 一样，但 data 直接借用源缓冲区，不复制
+pub struct ChunkRef<'a> {
+    length: u32,
+    chunk_type: ChunkType,
+    data: &'a [u8],
+    crc: u32,
+}
+
+impl<'a> ChunkRef<'a> {
+    // 从 bytes 开头解析一个 chunk，并把 bytes 往后移
+    pub fn read(bytes: &mut &'a [u8]) -> Result<Self> {
+        let (header, rest) = bytes.split_at_checked(8).ok_or("invalid chunk")?;
+        let length = u32::from_be_bytes(header[..4].try_into()?);
+        let chunk_type: ChunkType = <[u8; 4]>::try_from(&header[4..])?.try_into()?;
+
+        let (data, rest) = rest
+            .split_at_checked(Chunk::checked_length(length, Chunk::MAX_LENGTH)?)
+            .ok_or("invalid chunk")?;
+        let (crc, rest) = rest.split_at_checked(4).ok_or("invalid chunk")?;
+        let crc = u32::from_be_bytes(crc.try_into()?);
+
+        Chunk::check_crc(&chunk_type, data, crc)?;
+
+        *bytes = rest;
+        Ok(Self {
+            length,
+            chunk_type,
+            data,
+            crc,
+        })
+    }
+
+    pub fn length(&self) -> u32 {
+        self.length
+    }
+
+    pub fn chunk_type(&self) -> &ChunkType {
+        &self.chunk_type
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn crc(&self) -> u32 {
+        self.crc
+    }
+
+    pub fn data_as_string(&self) -> Result<String> {
+        String::from_utf8(self.data.to_vec()).map_err(Into::into)
+    }
+
+    pub fn to_owned(&self) -> Chunk {
+        Chunk::build(
+            self.length,
+            self.chunk_type.clone(),
+            self.data.to_vec(),
+            self.crc,
+        )
+    }
+}
+
+// 按需逐个解析 chunk；遇到错误返回一次 Err 之后就结束
+pub struct ChunkReader<'a> {
+    bytes: &'a [u8],
+    failed: bool,
+}
+
+impl<'a> ChunkReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            failed: false,
+        }
+    }
+}
+
+impl Iterator for ChunkReader<'_> {
+    type Item = Result<Chunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.bytes.is_empty() {
+            return None;
+        }
+
+        let chunk = Chunk::read_chunk(&mut self.bytes);
+        self.failed = chunk.is_err();
+        Some(chunk)
+    }
+}
+
+// 分段追加数据，CRC 跟着数据一起更新，finish 时不用再整体算一遍
+pub struct ChunkBuilder {
+    chunk_type: ChunkType,
+    data: Vec<u8>,
+    digest: Digest<'static, u32>,
+}
+
+impl ChunkBuilder {
+    pub fn new(chunk_type: ChunkType) -> Self {
+        let mut digest = png_crc_digest();
+        digest.update(&chunk_type.bytes());
+
+        Self {
+            chunk_type,
+            data: vec![],
+            digest,
+        }
+    }
+
+    pub fn push_data(&mut self, bytes: &[u8]) {
+        self.digest.update(bytes);
+        self.data.extend_from_slice(bytes);
+    }
+
+    pub fn finish(self) -> Chunk {
+        Chunk::build(
+            self.data.len() as u32,
+            self.chunk_type,
+            self.data,
+            self.digest.finalize(),
+        )
+    }
+}
+
 impl Display for Chunk {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        let data = self
-            .data_as_string()
-            .map(|s| format!("b\"{}\"", s))
-            .unwrap_or(format!("{:?}", self.data));
-
         write!(
             f,
             "Chunk {{ length: {}, chunk_type: b\"{}\", data: {}, crc: {} }}",
-            self.length, self.chunk_type, data, self.crc
+            self.length,
+            self.chunk_type,
+            self.data_repr(),
+            self.crc
         )
     }
 }
@@ -158,8 +517,6 @@ impl Display for Chunk {
 mod tests {
     use super::*;
 
-    use std::str::FromStr;
-
     #[test]
     fn test_new_chunk() {
         let chunk_type = ChunkType::from_str("RuSt").unwrap();
@@ -263,6 +620,275 @@ mod tests {
         assert!(chunk.is_err());
     }
 
+    #[test]
+    fn test_chunk_from_strings() {
+        let chunk =
+            Chunk::from_strings("RuSt", "This is where your secret message will be!").unwrap();
+        assert!(chunk == testing_chunk());
+        assert_eq!(chunk.crc(), 2882656334);
+
+        assert!(Chunk::from_strings("Ru1t", "message").is_err());
+        assert!(Chunk::from_strings("RuStt", "message").is_err());
+    }
+
+    #[test]
+    fn test_chunk_iend() {
+        let chunk = Chunk::iend();
+
+        assert!(chunk.is_iend());
+        assert_eq!(chunk.length(), 0);
+        assert_eq!(chunk.crc(), 0xAE426082);
+        assert!(!testing_chunk().is_iend());
+    }
+
+    #[test]
+    fn test_chunk_set_data() {
+        let mut chunk = testing_chunk();
+        chunk.set_data(b"New data".to_vec());
+
+        let expected = Chunk::new(ChunkType::from_str("RuSt").unwrap(), b"New data".to_vec());
+        assert_eq!(chunk.length(), 8);
+        assert_eq!(chunk.data(), b"New data");
+        assert_eq!(chunk.crc(), expected.crc());
+    }
+
+    #[test]
+    fn test_chunk_verify_invariants() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let data = b"This is where your secret message will be!".to_vec();
+        let chunk = Chunk::build(42, chunk_type, data, 2882656334);
+
+        chunk.verify_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "stale chunk crc")]
+    #[cfg(debug_assertions)]
+    fn test_chunk_verify_invariants_stale_crc() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let data = b"This is where your secret message will be!".to_vec();
+        let chunk = Chunk::build(42, chunk_type, data, 2882656333);
+
+        chunk.verify_invariants();
+    }
+
+    #[test]
+    fn test_chunk_as_bytes_verified() {
+        let chunk = testing_chunk();
+        assert_eq!(chunk.as_bytes_verified().unwrap(), chunk.as_bytes());
+
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let data = b"This is where your secret message will be!".to_vec();
+        let chunk = Chunk::build(42, chunk_type, data, 2882656333);
+
+        assert!(chunk.as_bytes_verified().is_err());
+        assert_eq!(chunk.as_bytes().len(), 54);
+    }
+
+    #[test]
+    fn test_chunk_write_to() {
+        let chunk = testing_chunk();
+        let mut bytes = vec![];
+        chunk.write_to(&mut bytes).unwrap();
+
+        assert_eq!(bytes, chunk.as_bytes());
+    }
+
+    #[test]
+    fn test_invalid_chunk_type_from_bytes() {
+        let data_length: u32 = 42;
+        let chunk_type = "Ru1t".as_bytes();
+        let message_bytes = "This is where your secret message will be!".as_bytes();
+        let crc: u32 = 2882656334;
+
+        let chunk_data: Vec<_> = data_length
+            .to_be_bytes()
+            .iter()
+            .chain(chunk_type.iter())
+            .chain(message_bytes.iter())
+            .chain(crc.to_be_bytes().iter())
+            .copied()
+            .collect();
+
+        let chunk = Chunk::try_from(chunk_data.as_ref());
+        assert!(chunk.is_err());
+    }
+
+    #[test]
+    fn test_chunk_detect_encoding() {
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), "café".into());
+        assert_eq!(chunk.detect_encoding(), Some("utf8"));
+
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"caf\xe9".to_vec());
+        assert_eq!(chunk.detect_encoding(), Some("latin1"));
+
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), vec![0xff, 0x00, 0x81]);
+        assert_eq!(chunk.detect_encoding(), None);
+    }
+
+    #[test]
+    fn test_chunk_crc_mismatch_message() {
+        let mut bytes = testing_chunk().as_bytes();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+
+        let err = Chunk::try_from(bytes.as_ref()).err().unwrap().to_string();
+        assert!(err.contains("RuSt"));
+        assert!(err.contains("declared abd1d84f"));
+        assert!(err.contains("computed abd1d84e"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_chunk_serde() {
+        let chunk = testing_chunk();
+        let json = serde_json::to_string(&chunk).unwrap();
+        assert!(json.contains("\"chunk_type\":\"RuSt\""));
+
+        let decoded: Chunk = serde_json::from_str(&json).unwrap();
+        assert!(decoded == chunk);
+
+        let json = r#"{"chunk_type":"RuSt","data":"not base64!"}"#;
+        assert!(serde_json::from_str::<Chunk>(json).is_err());
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn test_chunk_payload_sha256() {
+        let first = Chunk::from_strings("RuSt", "same data").unwrap();
+        let second = Chunk::from_strings("ruSt", "same data").unwrap();
+        let other = Chunk::from_strings("RuSt", "other data").unwrap();
+
+        assert_eq!(first.payload_sha256(), second.payload_sha256());
+        assert_ne!(first.payload_sha256(), other.payload_sha256());
+        assert_eq!(
+            Chunk::iend().payload_sha256()[..4],
+            [0xe3, 0xb0, 0xc4, 0x42]
+        );
+    }
+
+    #[test]
+    fn test_chunk_recompute_crc() {
+        let mut bytes = testing_chunk().as_bytes();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+
+        let mut chunk = Chunk::read_chunk_unverified(&mut bytes.as_slice(), 42).unwrap();
+        assert_eq!(chunk.crc(), 2882656335);
+        assert!(chunk.recompute_crc());
+        assert_eq!(chunk.crc(), 2882656334);
+        assert!(!chunk.recompute_crc());
+    }
+
+    #[test]
+    fn test_chunk_content_eq() {
+        let chunk = testing_chunk();
+        let stale = Chunk::build(
+            chunk.length(),
+            chunk.chunk_type().clone(),
+            chunk.data().to_vec(),
+            chunk.crc() ^ 1,
+        );
+
+        assert!(chunk.content_eq(&stale));
+        assert!(chunk != stale);
+        assert!(chunk == testing_chunk());
+
+        let other = Chunk::new(chunk.chunk_type().clone(), b"other".to_vec());
+        assert!(!chunk.content_eq(&other));
+    }
+
+    #[test]
+    fn test_chunk_from_vec() {
+        let bytes = testing_chunk().as_bytes();
+        let from_slice = Chunk::try_from(bytes.as_ref()).unwrap();
+        let from_vec = Chunk::try_from(bytes.clone()).unwrap();
+
+        assert_eq!(from_vec.as_bytes(), from_slice.as_bytes());
+        assert_eq!(from_vec.data(), from_slice.data());
+
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert!(Chunk::try_from(trailing).is_err());
+    }
+
+    #[test]
+    fn test_chunk_crc_status() {
+        let mut bytes = testing_chunk().as_bytes();
+        let (declared, computed, matches) = Chunk::crc_status(&bytes).unwrap();
+        assert_eq!(declared, 2882656334);
+        assert_eq!(declared, computed);
+        assert!(matches);
+
+        bytes[10] ^= 0xff;
+        let (declared, computed, matches) = Chunk::crc_status(&bytes).unwrap();
+        assert_eq!(declared, 2882656334);
+        assert_ne!(declared, computed);
+        assert!(!matches);
+
+        assert!(Chunk::crc_status(&bytes[..20]).is_err());
+    }
+
+    #[test]
+    fn test_chunk_oversized_length() {
+        let bytes: Vec<_> = u32::MAX
+            .to_be_bytes()
+            .iter()
+            .chain(b"RuSt".iter())
+            .copied()
+            .collect();
+
+        let err = Chunk::read_chunk(&mut bytes.as_slice()).err().unwrap();
+        assert!(err.to_string().contains("exceeds limit"));
+        assert!(ChunkRef::read(&mut bytes.as_slice()).is_err());
+
+        let bytes = testing_chunk().as_bytes();
+        assert!(Chunk::read_chunk_limited(&mut bytes.as_slice(), 8).is_err());
+        assert!(Chunk::read_chunk_limited(&mut bytes.as_slice(), 42).is_ok());
+    }
+
+    #[test]
+    fn test_chunk_reader() {
+        let first = Chunk::new(ChunkType::from_str("FrSt").unwrap(), b"first".to_vec());
+        let mut second = Chunk::new(ChunkType::from_str("SeCd").unwrap(), b"second".to_vec());
+        second.crc += 1;
+        let third = Chunk::new(ChunkType::from_str("ThRd").unwrap(), b"third".to_vec());
+
+        let bytes = [first.as_bytes(), second.as_bytes(), third.as_bytes()].concat();
+        let mut reader = ChunkReader::new(&bytes);
+
+        assert_eq!(reader.next().unwrap().unwrap().data(), b"first");
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+
+        let bytes = [first.as_bytes(), third.as_bytes()].concat();
+        let types: Vec<_> = ChunkReader::new(&bytes)
+            .map(|chunk| chunk.unwrap().chunk_type().to_string())
+            .collect();
+        assert_eq!(types, ["FrSt", "ThRd"]);
+    }
+
+    #[test]
+    fn test_chunk_ref() {
+        let chunk = testing_chunk();
+        let bytes = [chunk.as_bytes(), chunk.as_bytes()].concat();
+        let mut rest = bytes.as_slice();
+
+        let chunk_ref = ChunkRef::read(&mut rest).unwrap();
+        assert_eq!(rest.len(), chunk.as_bytes().len());
+        assert_eq!(chunk_ref.length(), 42);
+        assert_eq!(&chunk_ref.chunk_type().to_string(), "RuSt");
+        assert_eq!(chunk_ref.crc(), 2882656334);
+        assert_eq!(
+            chunk_ref.data_as_string().unwrap(),
+            "This is where your secret message will be!"
+        );
+        assert_eq!(chunk_ref.to_owned().as_bytes(), chunk.as_bytes());
+
+        let mut truncated = &bytes[..20];
+        assert!(ChunkRef::read(&mut truncated).is_err());
+    }
+
     #[test]
     fn test_chunk_trait_impls() {
         let data_length: u32 = 42;
@@ -282,4 +908,34 @@ mod tests {
         let chunk: Chunk = TryFrom::try_from(chunk_data.as_ref()).unwrap();
         _ = format!("{}", chunk);
     }
+
+    #[test]
+    fn test_chunk_builder() {
+        let mut builder = ChunkBuilder::new(ChunkType::from_str("ruSt").unwrap());
+        for piece in ["This is where ", "your secret message ", "will be!"] {
+            builder.push_data(piece.as_bytes());
+        }
+        let chunk = builder.finish();
+
+        let expected =
+            Chunk::from_strings("ruSt", "This is where your secret message will be!").unwrap();
+        assert!(chunk == expected);
+        assert_eq!(chunk.crc(), expected.crc());
+    }
+
+    #[test]
+    fn test_chunk_is_valid_bytes() {
+        let bytes = testing_chunk().as_bytes();
+        assert!(Chunk::is_valid_bytes(&bytes));
+
+        let mut corrupt = bytes.clone();
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 0xff;
+        assert!(!Chunk::is_valid_bytes(&corrupt));
+
+        assert!(!Chunk::is_valid_bytes(&bytes[..bytes.len() - 1]));
+        assert!(!Chunk::is_valid_bytes(&bytes[..3]));
+        assert!(!Chunk::is_valid_bytes(&[]));
+        assert!(!Chunk::is_valid_bytes(&[&bytes[..], &[0]].concat()));
+    }
 }
@@ -1,4 +1,5 @@
 use std::{
+    array,
     convert::TryFrom,
     fmt::{Display, Formatter, Result as FmtResult},
     str::FromStr,
@@ -6,18 +7,93 @@ use std::{
 
 use crate::{Error, Result};
 
-#[derive(Debug, PartialEq, Eq)]
+const KNOWN_CHUNK_TYPES: [(&str, &str); 25] = [
+    ("IHDR", "image header"),
+    ("PLTE", "palette"),
+    ("IDAT", "image data"),
+    ("IEND", "image trailer"),
+    ("cHRM", "primary chromaticities and white point"),
+    ("cICP", "coding-independent code points"),
+    ("gAMA", "image gamma"),
+    ("iCCP", "embedded ICC profile"),
+    ("mDCv", "mastering display color volume"),
+    ("cLLi", "content light level information"),
+    ("sBIT", "significant bits"),
+    ("sRGB", "standard RGB color space"),
+    ("bKGD", "background color"),
+    ("hIST", "image histogram"),
+    ("tRNS", "transparency"),
+    ("eXIf", "exchangeable image file profile"),
+    ("pHYs", "physical pixel dimensions"),
+    ("sPLT", "suggested palette"),
+    ("tIME", "image last-modification time"),
+    ("iTXt", "international textual data"),
+    ("tEXt", "textual data"),
+    ("zTXt", "compressed textual data"),
+    ("acTL", "animation control"),
+    ("fcTL", "frame control"),
+    ("fdAT", "frame data"),
+];
+
+// 规范里定义的 chunk 类型及简短说明
+pub fn known_chunk_types() -> &'static [(&'static str, &'static str)] {
+    &KNOWN_CHUNK_TYPES
+}
+
+/// A four-letter chunk type. The bytes are private, so the only way to get
+/// one is through the validating `TryFrom<[u8; 4]>` or `FromStr`.
+///
+/// ```compile_fail
+/// let chunk_type = pngme::chunk_type::ChunkType { bytes: [0, 1, 2, 3] };
+/// ```
+///
+/// ```
+/// use std::str::FromStr;
+/// use pngme::chunk_type::ChunkType;
+///
+/// assert!(ChunkType::try_from([0, 1, 2, 3]).is_err());
+/// assert_eq!(ChunkType::from_str("ruSt").unwrap().bytes(), *b"ruSt");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ChunkType {
     bytes: [u8; 4],
 }
 
 impl ChunkType {
+    // 标准类型的字节都是合法的，可以在编译期直接构造
+    pub const IHDR: ChunkType = ChunkType { bytes: *b"IHDR" };
+    pub const PLTE: ChunkType = ChunkType { bytes: *b"PLTE" };
+    pub const IDAT: ChunkType = ChunkType { bytes: *b"IDAT" };
+    pub const IEND: ChunkType = ChunkType { bytes: *b"IEND" };
+    pub const TEXT: ChunkType = ChunkType { bytes: *b"tEXt" };
+
+    /// Like `FromStr`, but also accepts ASCII digits. This is not PNG spec:
+    /// it's for derivative formats that put digits in their chunk types, and
+    /// `is_valid` still reports such types as invalid.
+    pub fn from_str_relaxed(s: &str) -> Result<ChunkType> {
+        check_four_ascii(s)?;
+        let bytes = <[u8; 4]>::try_from(s.as_bytes())?;
+        if let Some(&b) = bytes.iter().find(|b| !b.is_ascii_alphanumeric()) {
+            return Err(format!(
+                "invalid chunk type: byte {:#04x} is not an ASCII letter or digit",
+                b
+            )
+            .into());
+        }
+
+        Ok(Self { bytes })
+    }
+
     pub fn bytes(&self) -> [u8; 4] {
         self.bytes
     }
 
+    pub fn iter(&self) -> array::IntoIter<u8, 4> {
+        self.bytes.into_iter()
+    }
+
     pub fn is_valid(&self) -> bool {
-        self.bytes.iter().all(u8::is_ascii_alphabetic) && self.is_reserved_bit_valid()
+        self.iter().all(|b| b.is_ascii_alphabetic()) && self.is_reserved_bit_valid()
     }
 
     pub fn is_critical(&self) -> bool {
@@ -35,25 +111,127 @@ impl ChunkType {
     pub fn is_safe_to_copy(&self) -> bool {
         self.bytes[3] >> 5 & 1 == 1
     }
+
+    pub fn set_critical(&mut self, critical: bool) {
+        self.set_lowercase(0, !critical);
+    }
+
+    pub fn set_public(&mut self, public: bool) {
+        self.set_lowercase(1, !public);
+    }
+
+    pub fn set_safe_to_copy(&mut self, safe_to_copy: bool) {
+        self.set_lowercase(3, safe_to_copy);
+    }
+
+    // 字母的第 5 位就是大小写位，改大小写不会变成非字母
+    fn set_lowercase(&mut self, index: usize, lowercase: bool) {
+        if lowercase {
+            self.bytes[index].make_ascii_lowercase();
+        } else {
+            self.bytes[index].make_ascii_uppercase();
+        }
+    }
+
+    pub fn is_standard(&self) -> bool {
+        self.description().is_some()
+    }
+
+    pub fn description(&self) -> Option<&'static str> {
+        known_chunk_types()
+            .iter()
+            .find(|(chunk_type, _)| chunk_type.as_bytes() == self.bytes)
+            .map(|(_, description)| *description)
+    }
+
+    pub fn eq_ignoring_copy_bit(&self, other: &ChunkType) -> bool {
+        self.bytes[..3] == other.bytes[..3] && self.bytes[3].eq_ignore_ascii_case(&other.bytes[3])
+    }
+
+    pub fn category(&self) -> ChunkCategory {
+        match (self.is_critical(), self.is_public()) {
+            (true, true) => ChunkCategory::CriticalPublic,
+            (true, false) => ChunkCategory::CriticalPrivate,
+            (false, true) => ChunkCategory::AncillaryPublic,
+            (false, false) => ChunkCategory::AncillaryPrivate,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkCategory {
+    CriticalPublic,
+    CriticalPrivate,
+    AncillaryPublic,
+    AncillaryPrivate,
+}
+
+// 序列化成 4 个字符的字符串，反序列化时同样要求是合法类型
+#[cfg(feature = "serde")]
+impl serde::Serialize for ChunkType {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ChunkType {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        ChunkType::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl IntoIterator for &ChunkType {
+    type Item = u8;
+    type IntoIter = array::IntoIter<u8, 4>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
 }
 
 impl TryFrom<[u8; 4]> for ChunkType {
     type Error = Error;
 
     fn try_from(value: [u8; 4]) -> Result<Self> {
-        if !value.iter().all(u8::is_ascii_alphabetic) {
-            return Err("invalid chunk type".into());
+        if let Some(&b) = value.iter().find(|b| !b.is_ascii_alphabetic()) {
+            return Err(if b.is_ascii_graphic() || b == b' ' {
+                format!("invalid chunk type: {:?} is not an ASCII letter", b as char)
+            } else {
+                format!("invalid chunk type: byte {:#04x} is not an ASCII letter", b)
+            }
+            .into());
         }
 
         Ok(Self { bytes: value })
     }
 }
 
+// 按字节取的话，"Ruüt" 这种含多字节字符的字符串会被错误地切开
+fn check_four_ascii(s: &str) -> Result<()> {
+    if s.chars().count() != 4 || !s.is_ascii() {
+        return Err(format!(
+            "invalid chunk type {:?}: expected exactly 4 ASCII characters",
+            s
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
 impl FromStr for ChunkType {
     type Err = Error;
     
     // 妙
     fn from_str(s: &str) -> Result<Self> {
+        check_four_ascii(s)?;
         <[_; 4]>::try_from(s.as_bytes())?.try_into()
     }
 
@@ -76,7 +254,11 @@ impl FromStr for ChunkType {
 
 impl Display for ChunkType {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        write!(f, "{}", String::from_utf8_lossy(&self.bytes))
+        // 正常构造出来的一定是字母，这里只是兜底，不能 panic
+        match std::str::from_utf8(&self.bytes) {
+            Ok(s) => write!(f, "{}", s),
+            Err(_) => self.iter().try_for_each(|b| write!(f, "\\x{:02x}", b)),
+        }
     }
 }
 
@@ -92,6 +274,14 @@ mod tests {
         assert_eq!(expected, actual.bytes());
     }
 
+    #[test]
+    fn test_chunk_type_display_never_panics() {
+        let chunk_type = ChunkType {
+            bytes: [0xff, b'u', 0xfe, b't'],
+        };
+        assert_eq!(chunk_type.to_string(), "\\xff\\x75\\xfe\\x74");
+    }
+
     #[test]
     fn test_chunk_type_from_str() {
         let expected = ChunkType::try_from([82, 117, 83, 116]).unwrap();
@@ -115,6 +305,41 @@ mod tests {
         assert!(chunk.is_err());
     }
 
+    #[test]
+    fn test_chunk_type_from_str_relaxed() {
+        assert!(ChunkType::from_str("ft00").is_err());
+
+        let chunk_type = ChunkType::from_str_relaxed("ft00").unwrap();
+        assert_eq!(chunk_type.to_string(), "ft00");
+        assert!(!chunk_type.is_critical());
+        assert!(!chunk_type.is_valid());
+
+        assert!(ChunkType::from_str_relaxed("ft0!").is_err());
+        assert!(ChunkType::from_str_relaxed("ft0").is_err());
+    }
+
+    #[test]
+    fn test_chunk_type_multibyte_error() {
+        let err = ChunkType::from_str("Ruüt").unwrap_err().to_string();
+        assert!(err.contains("exactly 4 ASCII characters"), "{}", err);
+        assert!(err.contains("Ruüt"), "{}", err);
+
+        assert!(ChunkType::from_str("Rüt").is_err());
+        assert!(ChunkType::from_str_relaxed("R0üt").is_err());
+        assert!(ChunkType::from_str("RuStX").is_err());
+    }
+
+    #[test]
+    fn test_invalid_chunk_type_error() {
+        let err = ChunkType::from_str("Ru@t").unwrap_err().to_string();
+        assert!(err.contains("'@'"), "{}", err);
+
+        let err = ChunkType::try_from([82, 117, 0, 116])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("0x00"), "{}", err);
+    }
+
     #[test]
     fn test_chunk_type_is_critical() {
         let chunk = ChunkType::from_str("RuSt").unwrap();
@@ -163,6 +388,135 @@ mod tests {
         assert!(!chunk.is_safe_to_copy());
     }
 
+    #[test]
+    fn test_chunk_type_set_critical() {
+        let mut chunk = ChunkType::from_str("RuSt").unwrap();
+        chunk.set_critical(false);
+        assert!(!chunk.is_critical());
+        assert_eq!(&chunk.to_string(), "ruSt");
+
+        chunk.set_critical(true);
+        assert!(chunk.is_critical());
+        assert_eq!(&chunk.to_string(), "RuSt");
+    }
+
+    #[test]
+    fn test_chunk_type_set_public() {
+        let mut chunk = ChunkType::from_str("RuSt").unwrap();
+        chunk.set_public(true);
+        assert!(chunk.is_public());
+        assert_eq!(&chunk.to_string(), "RUSt");
+
+        chunk.set_public(false);
+        assert!(!chunk.is_public());
+        assert_eq!(&chunk.to_string(), "RuSt");
+    }
+
+    #[test]
+    fn test_chunk_type_set_safe_to_copy() {
+        let mut chunk = ChunkType::from_str("RuSt").unwrap();
+        chunk.set_safe_to_copy(false);
+        assert!(!chunk.is_safe_to_copy());
+        assert_eq!(&chunk.to_string(), "RuST");
+
+        chunk.set_safe_to_copy(true);
+        assert!(chunk.is_safe_to_copy());
+        assert!(chunk.is_valid());
+        assert_eq!(&chunk.to_string(), "RuSt");
+    }
+
+    #[test]
+    fn test_chunk_type_eq_ignoring_copy_bit() {
+        let safe = ChunkType::from_str("ruSt").unwrap();
+        let unsafe_ = ChunkType::from_str("ruST").unwrap();
+        assert!(safe.eq_ignoring_copy_bit(&unsafe_));
+        assert_ne!(safe, unsafe_);
+
+        let other = ChunkType::from_str("RuSt").unwrap();
+        assert!(!safe.eq_ignoring_copy_bit(&other));
+    }
+
+    #[test]
+    fn test_chunk_type_category() {
+        let chunk = ChunkType::from_str("IHDR").unwrap();
+        assert_eq!(chunk.category(), ChunkCategory::CriticalPublic);
+
+        let chunk = ChunkType::from_str("ruSt").unwrap();
+        assert_eq!(chunk.category(), ChunkCategory::AncillaryPrivate);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_chunk_type_serde() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let json = serde_json::to_string(&chunk_type).unwrap();
+        assert_eq!(json, "\"RuSt\"");
+        assert_eq!(
+            serde_json::from_str::<ChunkType>(&json).unwrap(),
+            chunk_type
+        );
+
+        assert!(serde_json::from_str::<ChunkType>("\"Ru1t\"").is_err());
+        assert!(serde_json::from_str::<ChunkType>("\"RuStt\"").is_err());
+    }
+
+    #[test]
+    fn test_chunk_type_consts() {
+        assert_eq!(ChunkType::IEND.to_string(), "IEND");
+        assert_eq!(ChunkType::IHDR, ChunkType::from_str("IHDR").unwrap());
+        assert_eq!(ChunkType::TEXT, ChunkType::from_str("tEXt").unwrap());
+
+        for chunk_type in [
+            ChunkType::IHDR,
+            ChunkType::PLTE,
+            ChunkType::IDAT,
+            ChunkType::IEND,
+            ChunkType::TEXT,
+        ] {
+            assert!(chunk_type.is_valid());
+            assert!(chunk_type.is_standard());
+        }
+    }
+
+    #[test]
+    fn test_chunk_type_iter() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let bytes: Vec<u8> = chunk_type.iter().collect();
+        assert_eq!(bytes, chunk_type.bytes());
+
+        let mut upper = 0;
+        for b in &chunk_type {
+            upper += b.is_ascii_uppercase() as usize;
+        }
+        assert_eq!(upper, 2);
+        assert!(ChunkType::from_str("IDAT")
+            .unwrap()
+            .iter()
+            .all(|b| b.is_ascii_uppercase()));
+    }
+
+    #[test]
+    fn test_known_chunk_types() {
+        let (_, description) = known_chunk_types()
+            .iter()
+            .find(|(chunk_type, _)| *chunk_type == "IDAT")
+            .unwrap();
+        assert!(!description.is_empty());
+
+        assert_eq!(
+            ChunkType::from_str("tEXt").unwrap().description(),
+            Some("textual data")
+        );
+        assert_eq!(ChunkType::from_str("ruSt").unwrap().description(), None);
+    }
+
+    #[test]
+    fn test_chunk_type_is_standard() {
+        assert!(ChunkType::from_str("tEXt").unwrap().is_standard());
+        assert!(ChunkType::from_str("IDAT").unwrap().is_standard());
+        assert!(!ChunkType::from_str("ruSt").unwrap().is_standard());
+    }
+
     #[test]
     fn test_chunk_type_string() {
         let chunk = ChunkType::from_str("RuSt").unwrap();
@@ -1,49 +1,2011 @@
-use std::{fs, str::FromStr};
+use std::{
+    fs::{self, File},
+    io::{self, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Instant,
+};
+
+use base64::Engine;
 
 use crate::{
-    args::{DecodeArgs, EncodeArgs, PrintArgs, RemoveArgs},
+    args::{
+        ChecksumArgs, DecodeArgs, DedupArgs, DiffArgs, DumpTextArgs, EncodeArgs, ExplodeArgs,
+        ExtractChunkArgs, FixArgs, GrepArgs, ImplodeArgs, InjectChunkArgs, InteractiveArgs,
+        LayoutArgs, MergeArgs, MetadataArgs, MinifyArgs, NormalizeArgs, PrintArgs, RemoveArgs,
+        ScanArgs, StatsArgs, VerifyArgs, DEFAULT_CHUNK_TYPE,
+    },
     chunk::Chunk,
     chunk_type::ChunkType,
-    png::Png,
-    Result,
+    format::{escape_bytes, human_bytes, paint, parse_hex, to_hex, CYAN, RED, YELLOW},
+    log,
+    metadata::{Bkgd, Histogram, ImageHeader, Palette, Srgb, SuggestedPalette},
+    png::{Png, RecoveryError},
+    png_crc_digest, repl,
+    sink::PngSink,
+    text, Result,
 };
 
-pub fn encode(args: &EncodeArgs) -> Result<()> {
-    let mut png: Png = fs::read(&args.file_path)?.as_slice().try_into()?;
-    png.append_chunk(Chunk::new(
-        ChunkType::from_str(&args.chunk_type)?,
-        args.message.as_bytes().into(),
+// 对所有子命令都生效的参数，由 main 从 Cli 里取出来传下去
+#[derive(Clone, Copy, Default)]
+pub struct Options {
+    pub recompute_crc: bool,
+    pub timings: bool,
+    pub no_inplace: bool,
+}
+
+impl Options {
+    fn timing(&self, step: &str, start: Instant) {
+        if self.timings {
+            log::timing(step, start);
+        }
+    }
+}
+
+// 所有写文件的命令都走这里，路径为 - 时写到标准输出
+pub(crate) fn write_png(png: &mut Png, path: &str, options: &Options) -> Result<()> {
+    if path == "-" {
+        write_png_to(png, &mut io::stdout(), options)
+    } else {
+        write_png_to(png, &mut File::create(path)?, options)
+    }
+}
+
+// --recompute-crc 时先重算 CRC
+pub(crate) fn write_png_to<S: PngSink>(
+    png: &mut Png,
+    sink: &mut S,
+    options: &Options,
+) -> Result<()> {
+    if options.recompute_crc {
+        let start = Instant::now();
+        png.recompute_crcs();
+        options.timing("crc recompute", start);
+    }
+
+    let start = Instant::now();
+    sink.write_png(png)?;
+    options.timing("write", start);
+    Ok(())
+}
+
+// `encode img.png "secret"` 时唯一的位置参数会被 clap 当成 chunk_type，这里把它还原成消息
+fn encode_target(args: &EncodeArgs) -> (&str, Option<&str>) {
+    match (&args.chunk_type, &args.message) {
+        (Some(message), None) if args.file.is_none() && args.hex.is_none() => {
+            (DEFAULT_CHUNK_TYPE, Some(message))
+        }
+        (chunk_type, message) => (
+            chunk_type.as_deref().unwrap_or(DEFAULT_CHUNK_TYPE),
+            message.as_deref(),
+        ),
+    }
+}
+
+// --multi 的每一项都是 type=message
+fn parse_pair(pair: &str) -> Result<(&str, Option<&str>)> {
+    let (chunk_type, message) = pair
+        .split_once('=')
+        .ok_or_else(|| format!("expected type=message, got {:?}", pair))?;
+    ChunkType::from_str(chunk_type)?;
+
+    Ok((chunk_type, Some(message)))
+}
+
+pub fn encode(args: &EncodeArgs, options: &Options) -> Result<()> {
+    // 先校验，避免往已经损坏的文件里继续写；--force 时所有 chunk 原样保留，包括 CRC 不对的。
+    // 根本不是 PNG 的文件 --force 也没用，直接报错
+    let start = Instant::now();
+    let bytes = read_png_bytes(&args.file_path)?;
+    let (mut png, errors) = Png::try_from_recover(&bytes);
+    if let Some(error) = errors.first() {
+        if !args.force {
+            return Err(format!(
+                "{} is corrupt: {}; use --force to encode anyway",
+                args.file_path, error
+            )
+            .into());
+        }
+        for error in errors.iter() {
+            log::warn(&error.to_string());
+        }
+        png = Png::read_unverified(&bytes)?;
+    }
+    options.timing("parse", start);
+    let targets = if args.multi.is_empty() {
+        let (chunk_type, message) = encode_target(args);
+        vec![(chunk_type, message)]
+    } else {
+        args.multi
+            .iter()
+            .map(|pair| parse_pair(pair))
+            .collect::<Result<_>>()?
+    };
+    let image = args.verify_image_unchanged.then(|| image_bytes(&png));
+    let size_before = png.total_size();
+
+    let start = Instant::now();
+    let mut written = vec![];
+    for (chunk_type, message) in targets {
+        if ChunkType::from_str(chunk_type)?.is_critical() {
+            let message = format!(
+                "{} is a critical chunk type, writing to it may corrupt the image",
+                chunk_type
+            );
+            if !args.force {
+                return Err(format!("{} (use --force to override)", message).into());
+            }
+            log::warn(&message);
+        }
+        if args.if_absent && png.chunk_by_type(chunk_type).is_some() {
+            log::info(&format!(
+                "{} chunk already exists, nothing to do",
+                chunk_type
+            ));
+            continue;
+        }
+        let data = match (&args.file, &args.hex, message) {
+            (Some(file), _, _) => fs::read(file)?,
+            (None, Some(hex), _) => parse_hex(hex)?,
+            (None, None, Some(message)) => message.as_bytes().into(),
+            (None, None, None) => return Err("missing message".into()),
+        };
+        let data = match &args.xor {
+            Some(key) => xor(&data, key.as_bytes())?,
+            None => data,
+        };
+        let data = if args.length_prefixed {
+            with_length_prefix(data)?
+        } else {
+            data
+        };
+        let length = data.len();
+
+        if args.replace_data {
+            png.chunk_by_type_mut(chunk_type)
+                .ok_or("non-existent chunk")?
+                .set_data(data);
+        } else {
+            png.append_chunk(Chunk::new(ChunkType::from_str(chunk_type)?, data));
+        }
+        written.push((chunk_type, length));
+    }
+    if written.is_empty() {
+        return Ok(());
+    }
+    options.timing("mutation", start);
+
+    if let Some(image) = image {
+        let encoded: Png = png.as_bytes().as_slice().try_into()?;
+        if image_bytes(&encoded) != image {
+            return Err("image data changed".into());
+        }
+    }
+
+    let output_file = match &args.output_file {
+        Some(output_file) => output_file.clone(),
+        None => output_path(&args.file_path, options.no_inplace),
+    };
+    if args.backup && output_file == args.file_path {
+        backup(&args.file_path, args.force)?;
+    }
+    write_png(&mut png, &output_file, options)?;
+
+    let verb = if args.replace_data {
+        "Updated"
+    } else {
+        "Added"
+    };
+    log::info(&encode_summary(
+        verb,
+        &written,
+        size_before,
+        png.total_size(),
     ));
+    Ok(())
+}
 
-    fs::write(
-        args.output_file.as_ref().unwrap_or(&args.file_path),
-        png.as_bytes(),
-    )?;
+// Added chunk 'ruSt' (42 bytes); file is now 1078 bytes (was 1036)
+fn encode_summary(verb: &str, chunks: &[(&str, usize)], before: usize, after: usize) -> String {
+    let chunks: Vec<_> = chunks
+        .iter()
+        .map(|(chunk_type, length)| format!("chunk '{}' ({} bytes)", chunk_type, length))
+        .collect();
+
+    format!(
+        "{} {}; file is now {} bytes (was {})",
+        verb,
+        chunks.join(", "),
+        after,
+        before
+    )
+}
 
+// --no-inplace 时 img.png 写到 img.pngme.png，原文件不动
+fn output_path(file_path: &str, no_inplace: bool) -> String {
+    if !no_inplace {
+        return file_path.to_string();
+    }
+
+    let path = Path::new(file_path);
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("png");
+    path.with_extension(format!("pngme.{}", extension))
+        .to_string_lossy()
+        .into_owned()
+}
+
+// 原地修改前把原文件复制到 <file>.bak，已有备份时不覆盖
+fn backup(file_path: &str, force: bool) -> Result<()> {
+    let backup_path = format!("{}.bak", file_path);
+    if Path::new(&backup_path).exists() && !force {
+        return Err(format!(
+            "backup {} already exists; use --force to overwrite it",
+            backup_path
+        )
+        .into());
+    }
+
+    fs::copy(file_path, backup_path)?;
     Ok(())
 }
 
+// 签名不对的直接报错，不当作损坏的 PNG 去逐段恢复
+fn read_png_bytes(file_path: &str) -> Result<Vec<u8>> {
+    let bytes = fs::read(file_path)?;
+    if !bytes.starts_with(&Png::STANDARD_HEADER) {
+        return Err(Png::NOT_PNG.into());
+    }
+
+    Ok(bytes)
+}
+
+fn verify_file(file_path: &str) -> Result<(Png, Vec<RecoveryError>)> {
+    Ok(Png::try_from_recover(&read_png_bytes(file_path)?))
+}
+
+pub fn verify(args: &VerifyArgs) -> Result<()> {
+    let (png, errors) = verify_file(&args.file_path)?;
+    let mut problems = png.validate();
+    if args.ordering {
+        for (index, (chunk_type, legal)) in png.ordering_report().into_iter().enumerate() {
+            if !legal {
+                problems.push(format!(
+                    "{} chunk {} is out of order",
+                    chunk_type,
+                    index + 1
+                ));
+            }
+        }
+    }
+    if errors.is_empty() && problems.is_empty() {
+        println!("ok");
+        return Ok(());
+    }
+
+    for problem in problems.iter() {
+        println!("{}", problem);
+    }
+
+    // 错误信息里已经带了声明的和实际的 CRC
+    for error in errors.iter() {
+        println!("{}", error);
+    }
+    Err(format!(
+        "{} corrupt region(s), {} validation error(s) found",
+        errors.len(),
+        problems.len()
+    )
+    .into())
+}
+
+fn image_bytes(png: &Png) -> Vec<u8> {
+    png.chunks()
+        .iter()
+        .filter(|chunk| matches!(&chunk.chunk_type().bytes(), b"IHDR" | b"PLTE" | b"IDAT"))
+        .flat_map(Chunk::as_bytes)
+        .collect()
+}
+
+fn load(file_path: &str, index: Option<usize>) -> Result<Png> {
+    load_bytes(&fs::read(file_path)?, index)
+}
+
+fn load_bytes(bytes: &[u8], index: Option<usize>) -> Result<Png> {
+    match index {
+        Some(index) => Png::read_all(bytes)?
+            .into_iter()
+            .nth(index)
+            .ok_or_else(|| "non-existent image".into()),
+        None => bytes.try_into(),
+    }
+}
+
+fn decode_text(data: &[u8], encoding: &str, lossy: bool) -> Result<String> {
+    // Latin-1 每个字节正好对应同值的 Unicode 码点
+    if encoding == "latin1" {
+        Ok(data.iter().map(|&b| b as char).collect())
+    } else if lossy {
+        Ok(String::from_utf8_lossy(data).into_owned())
+    } else {
+        String::from_utf8(data.to_vec()).map_err(Into::into)
+    }
+}
+
+// 用重复的 key 逐字节异或，只是混淆，不是加密
+fn xor(data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+    if key.is_empty() {
+        return Err("empty xor key".into());
+    }
+
+    Ok(data
+        .iter()
+        .zip(key.iter().cycle())
+        .map(|(b, k)| b ^ k)
+        .collect())
+}
+
+// 4 字节大端长度 + 消息，多个工具往同一类型 chunk 里追加时也能分清边界
+fn with_length_prefix(data: Vec<u8>) -> Result<Vec<u8>> {
+    let length = u32::try_from(data.len())?;
+    Ok([&length.to_be_bytes()[..], &data].concat())
+}
+
+fn strip_length_prefix(data: &[u8]) -> Result<&[u8]> {
+    let (length, rest) = data.split_at_checked(4).ok_or("missing length prefix")?;
+    let length = u32::from_be_bytes(length.try_into()?) as usize;
+
+    rest.get(..length)
+        .ok_or_else(|| "message shorter than its length prefix".into())
+}
+
 pub fn decode(args: &DecodeArgs) -> Result<()> {
-    let png: Png = fs::read(&args.file_path)?.as_slice().try_into()?;
+    let png = load(&args.file_path, args.index)?;
     match png.chunk_by_type(&args.chunk_type) {
-        Some(chunk) => println!("{}", String::from_utf8_lossy(chunk.data())),
+        Some(chunk) => {
+            let data = if args.length_prefixed {
+                strip_length_prefix(chunk.data())?
+            } else {
+                chunk.data()
+            };
+            let data = match &args.xor {
+                Some(key) => xor(data, key.as_bytes())?,
+                None => data.to_vec(),
+            };
+
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(&render(&data, args)?)?;
+            stdout.flush()?;
+        }
         None => eprintln!("non-existent chunk type"),
     }
 
     Ok(())
 }
 
-pub fn remove(args: &RemoveArgs) -> Result<()> {
+// 除了 raw 之外都按一行文本输出
+fn render(data: &[u8], args: &DecodeArgs) -> Result<Vec<u8>> {
+    let text = match args.format.as_str() {
+        "raw" => return Ok(data.to_vec()),
+        "hex" => to_hex(data),
+        "base64" => base64::engine::general_purpose::STANDARD.encode(data),
+        _ => decode_text(data, &args.encoding, args.lossy)?,
+    };
+
+    if args.no_newline {
+        return Ok(text.into_bytes());
+    }
+    Ok(format!("{}\n", text).into_bytes())
+}
+
+pub fn remove(args: &RemoveArgs, options: &Options) -> Result<()> {
+    // --no-inplace 是全局参数，clap 没法让它和子命令的 --shred 互斥，只能在这里检查
+    if args.shred && options.no_inplace {
+        return Err(
+            "--shred rewrites the file in place and can't be used with --no-inplace".into(),
+        );
+    }
+
+    let start = Instant::now();
     let mut png: Png = fs::read(&args.file_path)?.as_slice().try_into()?;
-    png.remove_chunk(&args.chunk_type)?;
+    options.timing("parse", start);
+
+    let start = Instant::now();
+    let removed = png.remove_chunk(&args.chunk_type)?;
+    options.timing("mutation", start);
+    if args.shred {
+        return shred(&mut png, removed.data(), &args.file_path, options);
+    }
 
-    fs::write(&args.file_path, png.as_bytes())?;
+    let output_file = output_path(&args.file_path, options.no_inplace);
+    if args.backup && output_file == args.file_path {
+        backup(&args.file_path, args.force)?;
+    }
+    write_png(&mut png, &output_file, options)
+}
+
+// 新文件里不能再出现被删的数据。先把新文件写好落盘，再用 0 覆盖原文件，最后改名替换，
+// 中途出错也不会只剩一个全是 0 的文件
+fn shred(png: &mut Png, removed: &[u8], file_path: &str, options: &Options) -> Result<()> {
+    if !removed.is_empty() && png.as_bytes().windows(removed.len()).any(|w| w == removed) {
+        return Err("the removed data still appears elsewhere in the file; nothing written".into());
+    }
+
+    let temp_path = shred_temp_path(file_path);
+    if let Err(e) = write_synced(png, &temp_path, options) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+
+    zero_and_replace(file_path, &temp_path)
+        .map_err(|e| format!("{}; the new file was kept at {}", e, temp_path.display()).into())
+}
+
+fn shred_temp_path(file_path: &str) -> PathBuf {
+    let path = Path::new(file_path);
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.shred.tmp", name))
+}
+
+fn write_synced(png: &mut Png, path: &Path, options: &Options) -> Result<()> {
+    let mut file = File::create(path)?;
+    write_png_to(png, &mut file, options)?;
+    file.sync_all()?;
     Ok(())
 }
 
+fn zero_and_replace(file_path: &str, temp_path: &Path) -> Result<()> {
+    let mut file = fs::OpenOptions::new().write(true).open(file_path)?;
+    let length = file.metadata()?.len();
+    io::copy(&mut io::repeat(0).take(length), &mut file)?;
+    file.sync_all()?;
+
+    fs::rename(temp_path, file_path)?;
+    Ok(())
+}
+
+fn describe_chunk(chunk: &Chunk, args: &PrintArgs) -> String {
+    let length = if args.bytes {
+        chunk.length().to_string()
+    } else {
+        human_bytes(chunk.length() as usize)
+    };
+
+    let color = match args.color.as_str() {
+        "always" => true,
+        "never" => false,
+        _ => io::stdout().is_terminal(),
+    };
+    let chunk_type = chunk.chunk_type().to_string();
+    let chunk_type = if !chunk.has_valid_crc() {
+        paint(&chunk_type, RED, color)
+    } else if chunk.chunk_type().is_critical() {
+        paint(&chunk_type, YELLOW, color)
+    } else {
+        paint(&chunk_type, CYAN, color)
+    };
+
+    format!(
+        "{} {:>10}  crc: {:08x}  data: {}",
+        chunk_type,
+        length,
+        chunk.crc(),
+        if args.lossy {
+            format!("b\"{}\"", String::from_utf8_lossy(chunk.data()))
+        } else if args.escape {
+            format!("b\"{}\"", escape_bytes(chunk.data()))
+        } else {
+            chunk.data_repr()
+        }
+    )
+}
+
+const SIGNATURE_SCAN_LIMIT: usize = 1024;
+
+// 解析失败时尽量恢复，并说明有多少字节没对上；--strict 时直接报错
+fn load_for_print(args: &PrintArgs) -> Result<Png> {
+    let bytes = fs::read(&args.file_path)?;
+    let bytes = if args.scan_signature {
+        let offset = Png::find_signature(&bytes, SIGNATURE_SCAN_LIMIT)
+            .ok_or("no PNG signature found in the first 1 KiB")?;
+        if offset > 0 {
+            log::info(&format!("found PNG signature at offset {}", offset));
+        }
+        &bytes[offset..]
+    } else {
+        &bytes[..]
+    };
+    if args.index.is_some() {
+        return load_bytes(bytes, args.index);
+    }
+
+    let err = match Png::try_from(bytes) {
+        Ok(png) => return Ok(png),
+        Err(err) => err,
+    };
+    if !bytes.starts_with(&Png::STANDARD_HEADER) {
+        return Err(err);
+    }
+
+    let (png, _) = Png::try_from_recover(bytes);
+    if let Err(discrepancy) = png.check_length(bytes.len()) {
+        let message = format!("{}: {}", err, discrepancy);
+        if args.strict {
+            return Err(message.into());
+        }
+        log::warn(&message);
+    }
+
+    // 结构完整、只是 CRC 不对时保留原来的 chunk，好在输出里标出来
+    Ok(Png::read_unverified(bytes).unwrap_or(png))
+}
+
 pub fn print(args: &PrintArgs) -> Result<()> {
+    let png = load_for_print(args)?;
+    println!("header: {:?}", png.header());
+    for chunk in png.chunks() {
+        println!("{}", describe_chunk(chunk, args));
+    }
+
+    Ok(())
+}
+
+pub fn normalize(args: &NormalizeArgs, options: &Options) -> Result<()> {
+    let start = Instant::now();
+    let mut png: Png = fs::read(&args.file_path)?.as_slice().try_into()?;
+    options.timing("parse", start);
+
+    let start = Instant::now();
+    let moved = png.normalize();
+    options.timing("mutation", start);
+    for chunk_type in moved {
+        println!("moved {}", chunk_type);
+    }
+
+    write_png(&mut png, &args.file_path, options)
+}
+
+pub fn dedup(args: &DedupArgs, options: &Options) -> Result<()> {
+    let start = Instant::now();
+    let mut png: Png = fs::read(&args.file_path)?.as_slice().try_into()?;
+    options.timing("parse", start);
+
+    let start = Instant::now();
+    let removed = png.dedup();
+    options.timing("mutation", start);
+    println!("removed {} duplicate chunk(s)", removed);
+
+    write_png(&mut png, &args.file_path, options)
+}
+
+pub fn minify(args: &MinifyArgs, options: &Options) -> Result<()> {
+    let start = Instant::now();
+    let bytes = fs::read(&args.file_path)?;
+    let mut png: Png = bytes.as_slice().try_into()?;
+    options.timing("parse", start);
+
+    let start = Instant::now();
+    if args.merge_idat {
+        png.merge_idat();
+    }
+    options.timing("mutation", start);
+
+    let output_file = args.output_file.as_ref().unwrap_or(&args.file_path);
+    write_png(&mut png, output_file, options)?;
+    println!("{} -> {} bytes", bytes.len(), png.total_size());
+
+    Ok(())
+}
+
+pub fn scan(args: &ScanArgs) -> Result<()> {
+    // 只看类型和长度，大文件也不用整个读进内存
+    let suspicious: Vec<_> = Png::scan_types(File::open(&args.file_path)?)?
+        .into_iter()
+        .filter(|(chunk_type, _)| !chunk_type.is_critical() && !chunk_type.is_standard())
+        .collect();
+    if suspicious.is_empty() {
+        println!("no hidden chunks found");
+    }
+
+    for (chunk_type, length) in suspicious {
+        println!("{} {}  (unknown chunk type)", chunk_type, length);
+    }
+
+    Ok(())
+}
+
+pub fn extract_chunk(args: &ExtractChunkArgs) -> Result<()> {
+    extract_chunk_to(args, io::stdout().lock())
+}
+
+fn extract_chunk_to<W: Write>(args: &ExtractChunkArgs, mut writer: W) -> Result<()> {
+    let png: Png = fs::read(&args.file_path)?.as_slice().try_into()?;
+    png.chunk_by_type(&args.chunk_type)
+        .ok_or("non-existent chunk type")?
+        .write_to(&mut writer)?;
+
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn inject_chunk(args: &InjectChunkArgs, options: &Options) -> Result<()> {
+    inject_chunk_from(args, io::stdin().lock(), options)
+}
+
+fn inject_chunk_from<R: Read>(
+    args: &InjectChunkArgs,
+    mut reader: R,
+    options: &Options,
+) -> Result<()> {
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+
+    let start = Instant::now();
+    let mut png: Png = fs::read(&args.file_path)?.as_slice().try_into()?;
+    let chunk = bytes.as_slice().try_into()?;
+    options.timing("parse", start);
+
+    let start = Instant::now();
+    png.append_chunk(chunk);
+    options.timing("mutation", start);
+
+    write_png(&mut png, &args.file_path, options)
+}
+
+// 修改是交互式的，只统计解析和每次 save 的耗时
+pub fn interactive(args: &InteractiveArgs, options: &Options) -> Result<()> {
+    let start = Instant::now();
+    let mut png: Png = fs::read(&args.file_path)?.as_slice().try_into()?;
+    options.timing("parse", start);
+    repl::run(
+        &mut png,
+        &args.file_path,
+        options,
+        io::stdin().lock(),
+        io::stdout().lock(),
+    )
+}
+
+pub fn stats(args: &StatsArgs) -> Result<()> {
+    let png: Png = fs::read(&args.file_path)?.as_slice().try_into()?;
+    let stats = png.stats();
+
+    println!("total size: {}", human_bytes(stats.total_size()));
+    println!("chunks: {}", stats.chunk_count());
+    for (chunk_type, bytes) in stats.bytes_by_type() {
+        println!(
+            "  {} {:>10} {:>6.1}%",
+            chunk_type,
+            human_bytes(*bytes),
+            stats.percentage_of(chunk_type)
+        );
+    }
+    if let Some((chunk_type, bytes)) = stats.largest() {
+        println!("largest chunk: {} ({})", chunk_type, human_bytes(bytes));
+    }
+    println!(
+        "IDAT: {:.1}% of file, {} of image data",
+        stats.percentage_of("IDAT"),
+        human_bytes(png.data_size_of_type("IDAT"))
+    );
+
+    Ok(())
+}
+
+pub fn metadata(args: &MetadataArgs) -> Result<()> {
+    let png: Png = fs::read(&args.file_path)?.as_slice().try_into()?;
+    let header = ImageHeader::from_chunk(png.chunk_by_type("IHDR").ok_or("missing IHDR")?)?;
+    let palette = png
+        .chunk_by_type("PLTE")
+        .map(Palette::from_chunk)
+        .transpose()?;
+
+    for chunk in png.chunks() {
+        let chunk_type = chunk.chunk_type();
+        let value = match &chunk_type.bytes() {
+            b"IHDR" => format!(
+                "{}x{}, bit depth {}, color type {}",
+                header.width(),
+                header.height(),
+                header.bit_depth(),
+                header.color_type()
+            ),
+            b"PLTE" => format!("{} entries", Palette::from_chunk(chunk)?.len()),
+            b"sRGB" => Srgb::from_chunk(chunk)?.name().to_string(),
+            b"bKGD" => format!("{:?}", Bkgd::from_chunk(chunk, &header)?),
+            b"sPLT" => {
+                let palette = SuggestedPalette::from_chunk(chunk)?;
+                format!("{}, {} entries", palette.name(), palette.entries().len())
+            }
+            b"hIST" => {
+                let histogram = Histogram::from_chunk(chunk, palette.as_ref())?;
+                format!("{} entries", histogram.frequencies().len())
+            }
+            _ => human_bytes(chunk.length() as usize),
+        };
+
+        println!(
+            "{} ({}): {}",
+            chunk_type,
+            chunk_type.description().unwrap_or("unknown chunk type"),
+            value
+        );
+    }
+
+    Ok(())
+}
+
+pub fn diff(args: &DiffArgs) -> Result<()> {
+    let png: Png = fs::read(&args.file_path)?.as_slice().try_into()?;
+    let other: Png = fs::read(&args.other_file_path)?.as_slice().try_into()?;
+
+    let differences = diff_chunks(png.chunks(), other.chunks());
+    if differences.is_empty() {
+        println!("no differences");
+    }
+    for difference in differences {
+        println!("{}", difference);
+    }
+
+    Ok(())
+}
+
+// 按位置逐个比较内容，只报告不同的 chunk
+fn diff_chunks(chunks: &[Chunk], others: &[Chunk]) -> Vec<String> {
+    (0..chunks.len().max(others.len()))
+        .filter_map(|index| match (chunks.get(index), others.get(index)) {
+            (Some(chunk), Some(other)) if chunk.content_eq(other) => None,
+            (Some(chunk), Some(other)) => Some(format!(
+                "~ {} {} -> {}{}",
+                index,
+                chunk.chunk_type(),
+                other.chunk_type(),
+                same_data_note(chunk, other)
+            )),
+            (Some(chunk), None) => Some(format!("- {} {}", index, chunk.chunk_type())),
+            (None, Some(other)) => Some(format!("+ {} {}", index, other.chunk_type())),
+            (None, None) => None,
+        })
+        .collect()
+}
+
+// 类型变了但数据没变，比如只是改了 chunk 名字
+#[cfg(feature = "sha2")]
+fn same_data_note(chunk: &Chunk, other: &Chunk) -> &'static str {
+    if chunk.payload_sha256() == other.payload_sha256() {
+        " (same data)"
+    } else {
+        ""
+    }
+}
+
+#[cfg(not(feature = "sha2"))]
+fn same_data_note(_: &Chunk, _: &Chunk) -> &'static str {
+    ""
+}
+
+pub fn checksum(args: &ChecksumArgs) -> Result<()> {
+    let png: Png = fs::read(&args.file_path)?.as_slice().try_into()?;
+    println!("{}", checksum_of(&png, args.crc_only)?);
+
+    Ok(())
+}
+
+fn checksum_of(png: &Png, crc_only: bool) -> Result<String> {
+    // 按顺序把每个 chunk 的 CRC 再算一遍 CRC，调换顺序也能发现
+    if crc_only {
+        let mut digest = png_crc_digest();
+        for chunk in png.chunks() {
+            digest.update(&chunk.crc().to_be_bytes());
+        }
+        return Ok(format!("{:08x}", digest.finalize()));
+    }
+
+    png_sha256(png)
+}
+
+// 对规范化后的 as_bytes() 做哈希，同样内容的文件结果一样
+#[cfg(feature = "sha2")]
+fn png_sha256(png: &Png) -> Result<String> {
+    use sha2::Digest;
+
+    Ok(to_hex(&sha2::Sha256::digest(png.as_bytes())))
+}
+
+#[cfg(not(feature = "sha2"))]
+fn png_sha256(_: &Png) -> Result<String> {
+    Err("SHA-256 checksums need the sha2 feature; use --crc-only".into())
+}
+
+pub fn grep(args: &GrepArgs) -> Result<()> {
+    if args.pattern.is_empty() {
+        return Err("empty pattern".into());
+    }
+
+    let png: Png = fs::read(&args.file_path)?.as_slice().try_into()?;
+    let matches = grep_chunks(&png, args.pattern.as_bytes(), args.ignore_case);
+    if matches.is_empty() {
+        println!("no matches found");
+    }
+
+    for (chunk_type, offset) in matches {
+        println!("{} {}", chunk_type, offset);
+    }
+
+    Ok(())
+}
+
+// 返回 (chunk 类型, 在 chunk 数据里的偏移)
+fn grep_chunks(png: &Png, pattern: &[u8], ignore_case: bool) -> Vec<(String, usize)> {
+    png.chunks()
+        .iter()
+        .flat_map(|chunk| {
+            chunk
+                .data()
+                .windows(pattern.len())
+                .enumerate()
+                .filter(|(_, window)| {
+                    if ignore_case {
+                        window.eq_ignore_ascii_case(pattern)
+                    } else {
+                        *window == pattern
+                    }
+                })
+                .map(|(offset, _)| (chunk.chunk_type().to_string(), offset))
+        })
+        .collect()
+}
+
+// fix 总是重算 CRC，相当于隐含了 --recompute-crc
+pub fn fix(args: &FixArgs, options: &Options) -> Result<()> {
+    let start = Instant::now();
+    let mut png = Png::read_unverified(&fs::read(&args.file_path)?)?;
+    options.timing("parse", start);
+
+    let start = Instant::now();
+    let fixed = png.recompute_crcs();
+    options.timing("crc recompute", start);
+    if fixed.is_empty() {
+        println!("all crcs are valid");
+    }
+    for chunk_type in fixed {
+        println!("fixed crc of {}", chunk_type);
+    }
+
+    write_png(
+        &mut png,
+        args.output_file.as_ref().unwrap_or(&args.file_path),
+        options,
+    )
+}
+
+pub fn merge(args: &MergeArgs, options: &Options) -> Result<()> {
+    let start = Instant::now();
+    let mut png: Png = fs::read(&args.file_path)?.as_slice().try_into()?;
+    let other: Png = fs::read(&args.other_file_path)?.as_slice().try_into()?;
+    options.timing("parse", start);
+
+    let start = Instant::now();
+    let merged = png.merge_ancillary_from(&other, args.skip_existing);
+    options.timing("mutation", start);
+    if merged.is_empty() {
+        println!("no chunks to merge");
+    }
+    for chunk_type in merged {
+        println!("merged {}", chunk_type);
+    }
+
+    write_png(
+        &mut png,
+        args.output_file.as_ref().unwrap_or(&args.file_path),
+        options,
+    )
+}
+
+pub fn dump_text(args: &DumpTextArgs) -> Result<()> {
+    let png: Png = fs::read(&args.file_path)?.as_slice().try_into()?;
+    let lines: String = text::extract_all_text(&png)
+        .into_iter()
+        .map(|(keyword, text)| format!("{}:{}\n", keyword, text))
+        .collect();
+
+    fs::write(&args.output_file, lines)?;
+    Ok(())
+}
+
+// 每个 chunk 原样写成 NN_TYPE.chunk，方便用外部工具单独编辑
+pub fn explode(args: &ExplodeArgs) -> Result<()> {
     let png: Png = fs::read(&args.file_path)?.as_slice().try_into()?;
-    println!("{}", png);
+    fs::create_dir_all(&args.dir)?;
+
+    let width = png.chunks().len().to_string().len().max(2);
+    for (index, chunk) in png.chunks().iter().enumerate() {
+        let name = format!("{:0width$}_{}.chunk", index, chunk.chunk_type());
+        fs::write(Path::new(&args.dir).join(name), chunk.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+// 按文件名前面的序号重新拼起来
+pub fn implode(args: &ImplodeArgs, options: &Options) -> Result<()> {
+    let start = Instant::now();
+    let mut files = vec![];
+    for entry in fs::read_dir(&args.dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "chunk") {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let index: usize = name
+                .split('_')
+                .next()
+                .and_then(|index| index.parse().ok())
+                .ok_or_else(|| format!("{} doesn't start with a chunk number", name))?;
+            files.push((index, path));
+        }
+    }
+    files.sort();
+
+    let chunks = files
+        .into_iter()
+        .map(|(_, path)| Chunk::try_from(fs::read(&path)?))
+        .collect::<Result<_>>()?;
+    options.timing("parse", start);
+
+    let start = Instant::now();
+    let mut png = Png::from_chunks(chunks);
+    options.timing("mutation", start);
+    write_png(&mut png, &args.output_file, options)
+}
+
+pub fn layout(args: &LayoutArgs) -> Result<()> {
+    let png: Png = fs::read(&args.file_path)?.as_slice().try_into()?;
+
+    println!("{:>10}  {:>10}  type", "offset", "length");
+    for (offset, length, name) in layout_rows(&png) {
+        println!("{:>10}  {:>10}  {}", offset, length, name);
+    }
 
     Ok(())
 }
+
+// (偏移, 含长度/类型/CRC 在内的总长度, 名字)，第一行是 8 字节签名
+fn layout_rows(png: &Png) -> Vec<(usize, usize, String)> {
+    let chunks = png
+        .chunk_offsets()
+        .into_iter()
+        .zip(png.chunks())
+        .map(|(offset, chunk)| {
+            let length = chunk.length() as usize + 12;
+            (offset, length, chunk.chunk_type().to_string())
+        });
+
+    std::iter::once((0, png.header().len(), "signature".to_string()))
+        .chain(chunks)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{env, path::PathBuf, process};
+
+    use clap::Parser;
+
+    use crate::{
+        args::{Cli, Command},
+        test_utils,
+    };
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("pngme_{}_{}", process::id(), name))
+    }
+
+    fn write_testing_png(name: &str) -> String {
+        let mut chunks = test_utils::minimal_chunks();
+        chunks.insert(2, test_utils::chunk("ruSt", b"Old secret"));
+
+        let path = temp_path(name);
+        Png::from_chunks(chunks)
+            .write_to(File::create(&path).unwrap())
+            .unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn read_png(path: &str) -> Png {
+        fs::read(path).unwrap().as_slice().try_into().unwrap()
+    }
+
+    fn encode_args(file_path: &str, chunk_type: &str, message: &str) -> EncodeArgs {
+        EncodeArgs {
+            file_path: file_path.to_string(),
+            chunk_type: Some(chunk_type.to_string()),
+            message: Some(message.to_string()),
+            output_file: None,
+            file: None,
+            hex: None,
+            replace_data: false,
+            if_absent: false,
+            verify_image_unchanged: false,
+            force: false,
+            length_prefixed: false,
+            xor: None,
+            backup: false,
+            multi: vec![],
+        }
+    }
+
+    fn print_args(flags: &[&str]) -> PrintArgs {
+        print_args_for("image.png", flags)
+    }
+
+    fn print_args_for(path: &str, flags: &[&str]) -> PrintArgs {
+        let args = ["pngme", "print", path]
+            .into_iter()
+            .chain(flags.iter().copied());
+        match Cli::try_parse_from(args).unwrap().command {
+            Command::Print(args) => args,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_encode_replace_data() {
+        let path = write_testing_png("replace_data.png");
+        let data_path = temp_path("replace_data.bin");
+        fs::write(&data_path, b"New secret").unwrap();
+
+        let args = EncodeArgs {
+            message: None,
+            file: Some(data_path.to_string_lossy().into_owned()),
+            replace_data: true,
+            ..encode_args(&path, "ruSt", "")
+        };
+        encode(&args, &Options::default()).unwrap();
+
+        let png = read_png(&path);
+        let chunks: Vec<_> = png
+            .chunks()
+            .iter()
+            .filter(|chunk| chunk.chunk_type().to_string() == "ruSt")
+            .collect();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].data(), b"New secret");
+        assert!(!png.as_bytes().windows(10).any(|w| w == b"Old secret"));
+
+        fs::remove_file(path).unwrap();
+        fs::remove_file(data_path).unwrap();
+    }
+
+    #[test]
+    fn test_encode_replace_data_missing_chunk() {
+        let path = write_testing_png("replace_data_missing.png");
+        let args = EncodeArgs {
+            replace_data: true,
+            ..encode_args(&path, "NoNe", "message")
+        };
+
+        assert!(encode(&args, &Options::default()).is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_encode_verify_image_unchanged() {
+        let path = write_testing_png("verify_image_unchanged.png");
+        let before = read_png(&path).chunk_by_type("IDAT").unwrap().as_bytes();
+
+        let args = EncodeArgs {
+            verify_image_unchanged: true,
+            ..encode_args(&path, "ruSt", "message")
+        };
+        encode(&args, &Options::default()).unwrap();
+
+        let after = read_png(&path).chunk_by_type("IDAT").unwrap().as_bytes();
+        assert_eq!(before, after);
+
+        let args = EncodeArgs {
+            replace_data: true,
+            verify_image_unchanged: true,
+            ..encode_args(&path, "IDAT", "message")
+        };
+        assert!(encode(&args, &Options::default()).is_err());
+        assert_eq!(
+            read_png(&path).chunk_by_type("IDAT").unwrap().as_bytes(),
+            before
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_load_index() {
+        let path = temp_path("load_index.png");
+        let bytes = [
+            test_utils::minimal_png(),
+            test_utils::png_with_text("Title", "second"),
+        ]
+        .concat();
+        fs::write(&path, bytes).unwrap();
+        let path = path.to_string_lossy().into_owned();
+
+        assert!(load(&path, None).is_err());
+        assert!(load(&path, Some(0))
+            .unwrap()
+            .chunk_by_type("tEXt")
+            .is_none());
+        assert!(load(&path, Some(1))
+            .unwrap()
+            .chunk_by_type("tEXt")
+            .is_some());
+        assert!(load(&path, Some(2)).is_err());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_describe_chunk() {
+        let chunk = test_utils::chunk("IDAT", &[0; 1536]);
+
+        let args = print_args(&[]);
+        assert!(describe_chunk(&chunk, &args).contains("1.5 KiB"));
+
+        let args = print_args(&["--bytes"]);
+        assert!(describe_chunk(&chunk, &args).contains(" 1536 "));
+    }
+
+    #[test]
+    fn test_lossy_decoding() {
+        let data = b"valid \xff\xfe text";
+        assert!(decode_text(data, "utf8", false).is_err());
+        assert_eq!(
+            decode_text(data, "utf8", true).unwrap(),
+            "valid \u{fffd}\u{fffd} text"
+        );
+
+        let chunk = test_utils::chunk("ruSt", data);
+        assert!(!describe_chunk(&chunk, &print_args(&[])).contains('\u{fffd}'));
+        assert!(describe_chunk(&chunk, &print_args(&["--lossy"])).contains('\u{fffd}'));
+    }
+
+    #[test]
+    fn test_describe_chunk_color() {
+        let chunk = test_utils::chunk("ruSt", b"hey");
+        assert!(!describe_chunk(&chunk, &print_args(&["--color", "never"])).contains('\x1b'));
+        assert!(describe_chunk(&chunk, &print_args(&["--color", "always"])).starts_with("\x1b[36m"));
+
+        // CRC 不对的 chunk 用红色
+        let chunk = Chunk::read_chunk_unverified(&mut &b"\0\0\0\0IEND\0\0\0\0"[..], 0).unwrap();
+        assert!(describe_chunk(&chunk, &print_args(&["--color", "always"])).starts_with("\x1b[31m"));
+    }
+
+    #[test]
+    fn test_describe_chunk_escaped() {
+        let chunk = test_utils::chunk("ruSt", b"two\nlines\0");
+        let description = describe_chunk(&chunk, &print_args(&["--escape"]));
+
+        assert!(description.ends_with(r#"data: b"two\nlines\x00""#));
+        assert!(!description.contains('\n'));
+    }
+
+    #[test]
+    fn test_encode_decode_default_chunk_type() {
+        let path = temp_path("default_chunk_type.png");
+        fs::write(&path, test_utils::minimal_png()).unwrap();
+        let path = path.to_string_lossy().into_owned();
+
+        let cli = Cli::try_parse_from(["pngme", "encode", &path, "secret"]).unwrap();
+        match &cli.command {
+            Command::Encode(args) => encode(args, &Options::default()).unwrap(),
+            _ => unreachable!(),
+        }
+
+        let png = read_png(&path);
+        let chunk = png.chunk_by_type(DEFAULT_CHUNK_TYPE).unwrap();
+        assert_eq!(chunk.data(), b"secret");
+
+        let cli = Cli::try_parse_from(["pngme", "decode", &path]).unwrap();
+        match &cli.command {
+            Command::Decode(args) => assert_eq!(args.chunk_type, DEFAULT_CHUNK_TYPE),
+            _ => unreachable!(),
+        }
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_extract_inject_chunk() {
+        let source = write_testing_png("extract_chunk.png");
+        let target = temp_path("inject_chunk.png");
+        fs::write(&target, test_utils::minimal_png()).unwrap();
+        let target = target.to_string_lossy().into_owned();
+
+        let mut bytes = vec![];
+        let args = ExtractChunkArgs {
+            file_path: source.clone(),
+            chunk_type: "ruSt".to_string(),
+        };
+        extract_chunk_to(&args, &mut bytes).unwrap();
+        assert_eq!(bytes, test_utils::chunk("ruSt", b"Old secret").as_bytes());
+
+        let args = InjectChunkArgs {
+            file_path: target.clone(),
+        };
+        inject_chunk_from(&args, bytes.as_slice(), &Options::default()).unwrap();
+
+        let png = read_png(&target);
+        assert_eq!(png.chunk_by_type("ruSt").unwrap().data(), b"Old secret");
+        assert_eq!(&png.chunks()[3].chunk_type().to_string(), "IEND");
+
+        fs::remove_file(source).unwrap();
+        fs::remove_file(target).unwrap();
+    }
+
+    #[test]
+    fn test_encode_refuses_corrupt_file() {
+        let path = write_testing_png("encode_corrupt.png");
+        let mut bytes = fs::read(&path).unwrap();
+        let offset = read_png(&path).chunk_offsets()[1] + 8;
+        bytes[offset] ^= 0xff;
+        fs::write(&path, &bytes).unwrap();
+        let original = Png::read_unverified(&bytes).unwrap();
+
+        let mut args = encode_args(&path, "ruSt", "New secret");
+        assert!(encode(&args, &Options::default()).is_err());
+        assert!(verify(&VerifyArgs {
+            file_path: path.clone(),
+            ordering: false,
+        })
+        .is_err());
+
+        args.force = true;
+        log::take_warnings();
+        encode(&args, &Options::default()).unwrap();
+        assert!(log::take_warnings()[0].contains("crc mismatch in IDAT chunk"));
+
+        // 坏掉的 IDAT 原样留在输出里，不会被悄悄删掉
+        let png = Png::read_unverified(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(png.chunks().len(), 5);
+        assert_eq!(png.chunks()[3].data(), b"New secret");
+        let idat = png.chunk_by_type("IDAT").unwrap();
+        assert!(!idat.has_valid_crc());
+        assert_eq!(idat.data(), original.chunk_by_type("IDAT").unwrap().data());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_encode_hex() {
+        let path = write_testing_png("encode_hex.png");
+        let args = ["pngme", "encode", &path, "heXx", "--hex", "48656c6c6f"];
+        match Cli::try_parse_from(args).unwrap().command {
+            Command::Encode(args) => encode(&args, &Options::default()).unwrap(),
+            _ => unreachable!(),
+        }
+
+        assert_eq!(
+            read_png(&path).chunk_by_type("heXx").unwrap().data(),
+            b"Hello"
+        );
+
+        let args = ["pngme", "encode", &path, "heXx", "hi", "--hex", "00"];
+        assert!(Cli::try_parse_from(args).is_err());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_verify_duplicate_ihdr() {
+        let mut chunks = test_utils::minimal_chunks();
+        chunks.insert(1, test_utils::minimal_chunks().remove(0));
+        let path = temp_path("verify_duplicate.png");
+        Png::from_chunks(chunks)
+            .write_to(File::create(&path).unwrap())
+            .unwrap();
+
+        let args = VerifyArgs {
+            file_path: path.to_string_lossy().into_owned(),
+            ordering: false,
+        };
+        let err = verify(&args).unwrap_err();
+        assert!(err.to_string().contains("1 validation error"));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_length_prefixed_round_trip() {
+        let path = write_testing_png("length_prefixed.png");
+        let mut args = encode_args(&path, "prEf", "hello");
+        args.length_prefixed = true;
+        encode(&args, &Options::default()).unwrap();
+
+        let png = read_png(&path);
+        let data = png.chunk_by_type("prEf").unwrap().data();
+        assert_eq!(data, b"\0\0\0\x05hello");
+        assert_eq!(strip_length_prefix(data).unwrap(), b"hello");
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_length_prefix_ignores_trailing_garbage() {
+        let mut data = with_length_prefix(b"hello".to_vec()).unwrap();
+        data.extend_from_slice(b"garbage from another tool");
+
+        assert_eq!(strip_length_prefix(&data).unwrap(), b"hello");
+        assert!(strip_length_prefix(b"\0\0\0\x09short").is_err());
+        assert!(strip_length_prefix(b"\0\0").is_err());
+    }
+
+    #[test]
+    fn test_encode_backup() {
+        let path = write_testing_png("encode_backup.png");
+        let original = fs::read(&path).unwrap();
+        let backup_path = format!("{}.bak", path);
+
+        let mut args = encode_args(&path, "ruSt", "New secret");
+        args.backup = true;
+        encode(&args, &Options::default()).unwrap();
+
+        assert_eq!(fs::read(&backup_path).unwrap(), original);
+        assert_ne!(fs::read(&path).unwrap(), original);
+
+        assert!(encode(&args, &Options::default()).is_err());
+        assert_eq!(fs::read(&backup_path).unwrap(), original);
+
+        args.force = true;
+        encode(&args, &Options::default()).unwrap();
+        assert_ne!(fs::read(&backup_path).unwrap(), original);
+
+        fs::remove_file(path).unwrap();
+        fs::remove_file(backup_path).unwrap();
+    }
+
+    #[test]
+    fn test_output_path() {
+        assert_eq!(output_path("img.png", false), "img.png");
+        assert_eq!(output_path("dir/img.png", true), "dir/img.pngme.png");
+        assert_eq!(output_path("img", true), "img.pngme.png");
+    }
+
+    #[test]
+    fn test_remove_no_inplace() {
+        let path = write_testing_png("remove_no_inplace.png");
+        let original = fs::read(&path).unwrap();
+
+        let args = ["pngme", "--no-inplace", "remove", &path, "ruSt"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        let options = Options {
+            no_inplace: cli.no_inplace,
+            ..Options::default()
+        };
+        match cli.command {
+            Command::Remove(args) => remove(&args, &options).unwrap(),
+            _ => unreachable!(),
+        }
+
+        let derived = output_path(&path, true);
+        assert_eq!(fs::read(&path).unwrap(), original);
+        assert!(read_png(&derived).chunk_by_type("ruSt").is_none());
+
+        fs::remove_file(path).unwrap();
+        fs::remove_file(derived).unwrap();
+    }
+
+    #[test]
+    fn test_diff_chunks() {
+        let chunks = test_utils::minimal_chunks();
+        assert!(diff_chunks(&chunks, &test_utils::minimal_chunks()).is_empty());
+
+        let mut others = test_utils::minimal_chunks();
+        others[1].set_data(b"changed".to_vec());
+        others.push(test_utils::chunk("ruSt", b"extra"));
+
+        assert_eq!(
+            diff_chunks(&chunks, &others),
+            ["~ 1 IDAT -> IDAT", "+ 3 ruSt"]
+        );
+    }
+
+    #[test]
+    fn test_encode_if_absent() {
+        let path = write_testing_png("encode_if_absent.png");
+        let mut args = encode_args(&path, "abSt", "first");
+        args.if_absent = true;
+
+        encode(&args, &Options::default()).unwrap();
+        args.message = Some("second".to_string());
+        log::take_info();
+        encode(&args, &Options::default()).unwrap();
+        assert_eq!(
+            log::take_info(),
+            ["abSt chunk already exists, nothing to do"]
+        );
+
+        let png = read_png(&path);
+        let chunks: Vec<_> = png
+            .chunks()
+            .iter()
+            .filter(|chunk| chunk.chunk_type().to_string() == "abSt")
+            .collect();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].data(), b"first");
+
+        let args = [
+            "pngme",
+            "encode",
+            &path,
+            "x",
+            "--if-absent",
+            "--replace-data",
+        ];
+        assert!(Cli::try_parse_from(args).is_err());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_decode_latin1() {
+        let data = b"caf\xe9 cr\xe8me";
+        assert!(decode_text(data, "utf8", false).is_err());
+
+        let args = ["pngme", "decode", "image.png", "--encoding", "latin1"];
+        let args = match Cli::try_parse_from(args).unwrap().command {
+            Command::Decode(args) => args,
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            decode_text(data, &args.encoding, args.lossy).unwrap(),
+            "café crème"
+        );
+
+        let args = ["pngme", "decode", "image.png", "--encoding", "koi8"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_not_a_png_file() {
+        let path = temp_path("not_a_png.txt");
+        fs::write(&path, "just some text\n").unwrap();
+        let path = path.to_string_lossy().into_owned();
+
+        let args = ["pngme", "decode", &path];
+        let err = match Cli::try_parse_from(args).unwrap().command {
+            Command::Decode(args) => decode(&args).unwrap_err(),
+            _ => unreachable!(),
+        };
+        assert_eq!(err.to_string(), "not a PNG file (invalid signature)");
+
+        let mut args = encode_args(&path, "ruSt", "hi");
+        let err = encode(&args, &Options::default()).unwrap_err();
+        assert_eq!(err.to_string(), "not a PNG file (invalid signature)");
+        args.force = true;
+        log::take_warnings();
+        let err = encode(&args, &Options::default()).unwrap_err();
+        assert_eq!(err.to_string(), "not a PNG file (invalid signature)");
+        assert!(log::take_warnings().is_empty());
+
+        let args = ["pngme", "verify", &path];
+        let err = match Cli::try_parse_from(args).unwrap().command {
+            Command::Verify(args) => verify(&args).unwrap_err(),
+            _ => unreachable!(),
+        };
+        assert_eq!(err.to_string(), "not a PNG file (invalid signature)");
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_grep_chunks() {
+        let mut chunks = test_utils::minimal_chunks();
+        chunks.insert(2, test_utils::chunk("ruSt", b"my secret, your SECRET"));
+        let png = Png::from_chunks(chunks);
+
+        assert_eq!(
+            grep_chunks(&png, b"secret", false),
+            [("ruSt".to_string(), 3)]
+        );
+        assert_eq!(
+            grep_chunks(&png, b"secret", true),
+            [("ruSt".to_string(), 3), ("ruSt".to_string(), 16)]
+        );
+        assert!(grep_chunks(&png, b"missing", true).is_empty());
+    }
+
+    #[test]
+    fn test_write_png_recompute_crc() {
+        let path = write_testing_png("recompute_crc.png");
+        let mut bytes = fs::read(&path).unwrap();
+        let offset = bytes.len() - 12 - 4 - 1;
+        bytes[offset] ^= 0xff;
+
+        let mut png = Png::read_unverified(&bytes).unwrap();
+        let options = Options {
+            recompute_crc: true,
+            ..Options::default()
+        };
+        write_png(&mut png, &path, &options).unwrap();
+
+        assert!(Png::try_from(fs::read(&path).unwrap().as_ref()).is_ok());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_fix() {
+        let path = write_testing_png("fix.png");
+        let original = fs::read(&path).unwrap();
+        let mut bytes = original.clone();
+        let crc_offset = bytes.len() - 12 - 1;
+        bytes[crc_offset] ^= 0xff;
+        fs::write(&path, bytes).unwrap();
+
+        fix(
+            &FixArgs {
+                file_path: path.clone(),
+                output_file: None,
+            },
+            &Options::default(),
+        )
+        .unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), original);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_layout_rows() {
+        let png = Png::try_from(test_utils::minimal_png().as_ref()).unwrap();
+        let rows = layout_rows(&png);
+
+        assert_eq!(rows[0], (0, 8, "signature".to_string()));
+        assert_eq!(rows[1], (8, 25, "IHDR".to_string()));
+        assert_eq!(rows.len(), 4);
+
+        let (offset, length, _) = rows.last().unwrap();
+        assert_eq!(offset + length, png.total_size());
+    }
+
+    #[test]
+    fn test_decode_formats() {
+        let render_as = |format: &str| {
+            let args = ["pngme", "decode", "image.png", "--format", format];
+            match Cli::try_parse_from(args).unwrap().command {
+                Command::Decode(args) => render(b"Hi\xff", &args),
+                _ => unreachable!(),
+            }
+        };
+
+        assert!(render_as("text").is_err());
+        assert_eq!(render_as("hex").unwrap(), b"4869ff\n");
+        assert_eq!(render_as("base64").unwrap(), b"SGn/\n");
+        assert_eq!(render_as("raw").unwrap(), b"Hi\xff");
+
+        let args = ["pngme", "decode", "image.png", "--format", "octal"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_decode_no_newline() {
+        let message = "hello, world";
+        let args = ["pngme", "decode", "image.png", "--no-newline"];
+        let output = match Cli::try_parse_from(args).unwrap().command {
+            Command::Decode(args) => render(message.as_bytes(), &args).unwrap(),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(output.len(), message.len());
+    }
+
+    #[test]
+    fn test_encode_critical_chunk_type() {
+        let path = write_testing_png("encode_critical.png");
+        let original = fs::read(&path).unwrap();
+
+        let mut args = encode_args(&path, "IDAT", "x");
+        let err = encode(&args, &Options::default()).unwrap_err();
+        assert!(err.to_string().contains("IDAT is a critical chunk type"));
+        assert_eq!(fs::read(&path).unwrap(), original);
+
+        args.force = true;
+        encode(&args, &Options::default()).unwrap();
+        assert!(log::take_warnings()
+            .iter()
+            .any(|warning| warning.contains("critical")));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_write_png_to_buffer() {
+        let mut png = Png::try_from(test_utils::minimal_png().as_ref()).unwrap();
+        png.append_chunk(test_utils::chunk("ruSt", b"in memory"));
+
+        let mut buffer = vec![];
+        write_png_to(&mut png, &mut buffer, &Options::default()).unwrap();
+
+        let round_trip = Png::try_from(buffer.as_ref()).unwrap();
+        assert_eq!(
+            round_trip.chunk_by_type("ruSt").unwrap().data(),
+            b"in memory"
+        );
+    }
+
+    #[test]
+    fn test_xor_round_trip() {
+        let path = write_testing_png("xor.png");
+        let mut args = encode_args(&path, "xoRr", "attack at dawn");
+        args.xor = Some("k3y!".to_string());
+        encode(&args, &Options::default()).unwrap();
+
+        let png = read_png(&path);
+        let stored = png.chunk_by_type("xoRr").unwrap().data();
+        assert_ne!(stored, b"attack at dawn");
+        assert_eq!(stored[0], b'a' ^ b'k');
+        assert_eq!(stored[4], b'c' ^ b'k');
+
+        let args = ["pngme", "decode", &path, "xoRr", "--xor", "k3y!"];
+        let args = match Cli::try_parse_from(args).unwrap().command {
+            Command::Decode(args) => args,
+            _ => unreachable!(),
+        };
+        let decoded = xor(stored, args.xor.unwrap().as_bytes()).unwrap();
+        assert_eq!(decoded, b"attack at dawn");
+
+        assert!(xor(b"data", b"").is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_print_truncated() {
+        let path = write_testing_png("print_truncated.png");
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() - 5]).unwrap();
+
+        let png = load_for_print(&print_args_for(&path, &[])).unwrap();
+        assert!(png.chunk_by_type("ruSt").is_some());
+        assert!(log::take_warnings()
+            .iter()
+            .any(|warning| warning.contains("(truncated)")));
+
+        let err = load_for_print(&print_args_for(&path, &["--strict"]))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("parsed chunks account for"));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn test_diff_chunks_same_data() {
+        let chunks = [test_utils::chunk("ruSt", b"secret")];
+        let renamed = [test_utils::chunk("ruSx", b"secret")];
+        let changed = [test_utils::chunk("ruSx", b"other")];
+
+        assert_eq!(
+            diff_chunks(&chunks, &renamed),
+            ["~ 0 ruSt -> ruSx (same data)"]
+        );
+        assert_eq!(diff_chunks(&chunks, &changed), ["~ 0 ruSt -> ruSx"]);
+    }
+
+    #[test]
+    fn test_verify_ordering() {
+        let mut chunks = test_utils::minimal_chunks();
+        chunks.insert(2, test_utils::chunk("gAMA", &[0, 0, 177, 143]));
+        let path = temp_path("verify_ordering.png");
+        Png::from_chunks(chunks)
+            .write_to(File::create(&path).unwrap())
+            .unwrap();
+
+        let mut args = VerifyArgs {
+            file_path: path.to_string_lossy().into_owned(),
+            ordering: false,
+        };
+        assert!(verify(&args).is_ok());
+
+        args.ordering = true;
+        let err = verify(&args).unwrap_err();
+        assert!(err.to_string().contains("1 validation error"));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_merge() {
+        let path = write_testing_png("merge.png");
+        let target = temp_path("merge_target.png");
+        fs::write(&target, test_utils::minimal_png()).unwrap();
+
+        let mut args = MergeArgs {
+            file_path: target.to_string_lossy().into_owned(),
+            other_file_path: path.clone(),
+            output_file: None,
+            skip_existing: true,
+        };
+        merge(&args, &Options::default()).unwrap();
+        merge(&args, &Options::default()).unwrap();
+        let png = read_png(&args.file_path);
+        assert_eq!(png.chunk_by_type("ruSt").unwrap().data(), b"Old secret");
+        assert_eq!(png.chunks().len(), 4);
+
+        args.skip_existing = false;
+        merge(&args, &Options::default()).unwrap();
+        assert_eq!(read_png(&args.file_path).chunks().len(), 5);
+
+        fs::remove_file(path).unwrap();
+        fs::remove_file(target).unwrap();
+    }
+
+    #[test]
+    fn test_dump_text() {
+        let path = temp_path("dump_text.png");
+        let output = temp_path("dump_text.txt");
+        let mut png = Png::try_from(test_utils::png_with_text("Title", "dice").as_slice()).unwrap();
+        png.append_chunk(text::itxt("Author", "", "", "me", false).unwrap());
+        png.append_chunk(test_utils::chunk("ruSt", b"hey"));
+        fs::write(&path, png.as_bytes()).unwrap();
+
+        dump_text(&DumpTextArgs {
+            file_path: path.to_string_lossy().into_owned(),
+            output_file: output.to_string_lossy().into_owned(),
+        })
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(&output).unwrap(),
+            "Title:dice\nAuthor:me\nruSt:hey\n"
+        );
+
+        fs::remove_file(path).unwrap();
+        fs::remove_file(output).unwrap();
+    }
+
+    #[test]
+    fn test_dedup() {
+        let path = write_testing_png("dedup.png");
+        let mut png = read_png(&path);
+        png.append_chunk(test_utils::chunk("ruSt", b"Old secret"));
+        fs::write(&path, png.as_bytes()).unwrap();
+
+        dedup(
+            &DedupArgs {
+                file_path: path.clone(),
+            },
+            &Options::default(),
+        )
+        .unwrap();
+        let png = read_png(&path);
+        assert_eq!(png.chunks().len(), 4);
+        assert_eq!(png.chunk_by_type("ruSt").unwrap().data(), b"Old secret");
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_encode_multi() {
+        let path = write_testing_png("encode_multi.png");
+        let args = [
+            "pngme",
+            "encode",
+            &path,
+            "--multi",
+            "abSt=first",
+            "cdSt=a=b",
+        ];
+        let args = match Cli::try_parse_from(args).unwrap().command {
+            Command::Encode(args) => args,
+            _ => unreachable!(),
+        };
+        encode(&args, &Options::default()).unwrap();
+
+        let png = read_png(&path);
+        assert_eq!(png.chunk_by_type("abSt").unwrap().data(), b"first");
+        assert_eq!(png.chunk_by_type("cdSt").unwrap().data(), b"a=b");
+
+        // 有一项不合法就什么都不写
+        let original = fs::read(&path).unwrap();
+        let args = EncodeArgs {
+            chunk_type: None,
+            message: None,
+            multi: vec!["efSt=ok".to_string(), "e1St=bad".to_string()],
+            ..encode_args(&path, "", "")
+        };
+        assert!(encode(&args, &Options::default()).is_err());
+        assert_eq!(fs::read(&path).unwrap(), original);
+
+        let args = ["pngme", "encode", &path, "ruSt", "x", "--multi", "abSt=y"];
+        assert!(Cli::try_parse_from(args).is_err());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    fn checksum_pngs() -> [Png; 3] {
+        let png = Png::try_from(test_utils::minimal_png().as_slice()).unwrap();
+        let same = Png::try_from(test_utils::minimal_png().as_slice()).unwrap();
+        let mut other = png.clone();
+        other.append_chunk(test_utils::chunk("ruSt", b"hey"));
+        [png, same, other]
+    }
+
+    #[test]
+    fn test_checksum_crc_only() {
+        let [png, same, other] = checksum_pngs();
+        let checksum = checksum_of(&png, true).unwrap();
+
+        assert_eq!(checksum.len(), 8);
+        assert_eq!(checksum, checksum_of(&same, true).unwrap());
+        assert_ne!(checksum, checksum_of(&other, true).unwrap());
+
+        let mut chunks = other.chunks().to_vec();
+        chunks.swap(1, 2);
+        let reordered = Png::from_chunks(chunks);
+        assert_ne!(
+            checksum_of(&other, true).unwrap(),
+            checksum_of(&reordered, true).unwrap()
+        );
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn test_checksum_sha256() {
+        let [png, same, other] = checksum_pngs();
+        let checksum = checksum_of(&png, false).unwrap();
+
+        assert_eq!(checksum.len(), 64);
+        assert_eq!(checksum, checksum_of(&same, false).unwrap());
+        assert_ne!(checksum, checksum_of(&other, false).unwrap());
+    }
+
+    #[test]
+    fn test_remove_shred() {
+        let message = b"Old secret";
+        let path = write_testing_png("remove_shred.png");
+        let args = ["pngme", "remove", &path, "ruSt", "--shred"];
+        match Cli::try_parse_from(args).unwrap().command {
+            Command::Remove(args) => remove(&args, &Options::default()).unwrap(),
+            _ => unreachable!(),
+        }
+
+        let bytes = fs::read(&path).unwrap();
+        assert!(!bytes.windows(message.len()).any(|w| w == message));
+        assert!(read_png(&path).chunk_by_type("ruSt").is_none());
+        assert!(!shred_temp_path(&path).exists());
+
+        // 同样的数据还藏在别的 chunk 里时拒绝写入
+        let mut png = read_png(&path);
+        png.append_chunk(test_utils::chunk("ruSt", message));
+        png.append_chunk(test_utils::chunk("coPy", message));
+        fs::write(&path, png.as_bytes()).unwrap();
+        let args = ["pngme", "remove", &path, "ruSt", "--shred"];
+        match Cli::try_parse_from(args).unwrap().command {
+            Command::Remove(args) => assert!(remove(&args, &Options::default()).is_err()),
+            _ => unreachable!(),
+        }
+        assert_eq!(fs::read(&path).unwrap(), png.as_bytes());
+
+        let args = ["pngme", "remove", &path, "ruSt", "--shred", "--backup"];
+        assert!(Cli::try_parse_from(args).is_err());
+        let args = ["pngme", "--no-inplace", "remove", &path, "ruSt", "--shred"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        let options = Options {
+            no_inplace: cli.no_inplace,
+            ..Options::default()
+        };
+        match cli.command {
+            Command::Remove(args) => assert!(remove(&args, &options).is_err()),
+            _ => unreachable!(),
+        }
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_encode_summary() {
+        assert_eq!(
+            encode_summary("Added", &[("ruSt", 42)], 1036, 1078),
+            "Added chunk 'ruSt' (42 bytes); file is now 1078 bytes (was 1036)"
+        );
+        assert_eq!(
+            encode_summary("Updated", &[("abSt", 1), ("cdSt", 2)], 10, 13),
+            "Updated chunk 'abSt' (1 bytes), chunk 'cdSt' (2 bytes); file is now 13 bytes (was 10)"
+        );
+
+        let path = write_testing_png("encode_summary.png");
+        let before = fs::read(&path).unwrap().len();
+        log::take_info();
+        encode(
+            &encode_args(&path, "ruSt", "New secret"),
+            &Options::default(),
+        )
+        .unwrap();
+        let after = fs::read(&path).unwrap().len();
+
+        assert_eq!(after, before + 12 + 10);
+        assert_eq!(
+            log::take_info(),
+            [format!(
+                "Added chunk 'ruSt' (10 bytes); file is now {} bytes (was {})",
+                after, before
+            )]
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_print_scan_signature() {
+        let path = temp_path("scan_signature.png");
+        fs::write(
+            &path,
+            [b"\xef\xbb\xbf", &test_utils::minimal_png()[..]].concat(),
+        )
+        .unwrap();
+        let path = path.to_string_lossy().into_owned();
+
+        assert!(load_for_print(&print_args_for(&path, &[])).is_err());
+
+        log::take_info();
+        let png = load_for_print(&print_args_for(&path, &["--scan-signature"])).unwrap();
+        assert_eq!(png.chunks().len(), 3);
+        assert_eq!(log::take_info(), ["found PNG signature at offset 3"]);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_explode_implode() {
+        let path = write_testing_png("explode.png");
+        let dir = temp_path("explode_chunks");
+        let output = temp_path("imploded.png");
+        let dir_name = dir.to_string_lossy().into_owned();
+
+        explode(&ExplodeArgs {
+            file_path: path.clone(),
+            dir: dir_name.clone(),
+        })
+        .unwrap();
+        assert!(dir.join("00_IHDR.chunk").exists());
+        assert!(dir.join("03_IEND.chunk").exists());
+
+        implode(
+            &ImplodeArgs {
+                dir: dir_name,
+                output_file: output.to_string_lossy().into_owned(),
+            },
+            &Options::default(),
+        )
+        .unwrap();
+        assert_eq!(fs::read(&output).unwrap(), fs::read(&path).unwrap());
+
+        fs::remove_file(path).unwrap();
+        fs::remove_file(output).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_timings() {
+        let path = write_testing_png("timings.png");
+        let cli = Cli::try_parse_from(["pngme", "encode", &path, "hey", "--timings"]).unwrap();
+        assert!(cli.timings);
+
+        let options = Options {
+            timings: cli.timings,
+            ..Options::default()
+        };
+        log::take_timings();
+        match &cli.command {
+            Command::Encode(args) => encode(args, &options).unwrap(),
+            _ => unreachable!(),
+        }
+
+        let steps: Vec<_> = log::take_timings()
+            .iter()
+            .map(|line| line.split(' ').nth(1).unwrap().to_string())
+            .collect();
+        assert_eq!(steps, ["parse", "mutation", "write"]);
+
+        fs::remove_file(path).unwrap();
+    }
+}
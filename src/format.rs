@@ -0,0 +1,105 @@
+use crate::Result;
+
+const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+pub fn human_bytes(n: usize) -> String {
+    if n < 1024 {
+        return format!("{} B", n);
+    }
+
+    let mut size = n as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// 和 Rust 字节串的 Debug 输出一样，不可打印的字节写成 \xNN
+pub fn escape_bytes(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .flat_map(|&b| std::ascii::escape_default(b))
+        .map(char::from)
+        .collect()
+}
+
+pub const RED: &str = "31";
+pub const YELLOW: &str = "33";
+pub const CYAN: &str = "36";
+
+// 最简单的 ANSI 着色，关掉时原样返回
+pub fn paint(text: &str, color: &str, enabled: bool) -> String {
+    if enabled {
+        format!("\x1b[{}m{}\x1b[0m", color, text)
+    } else {
+        text.to_string()
+    }
+}
+
+fn hex_digit(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|digit| digit as u8)
+}
+
+pub fn parse_hex(hex: &str) -> Result<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return Err("hex string must have an even length".into());
+    }
+
+    hex.as_bytes()
+        .chunks_exact(2)
+        .map(|pair| match (hex_digit(pair[0]), hex_digit(pair[1])) {
+            (Some(high), Some(low)) => Ok(high << 4 | low),
+            _ => Err(format!("invalid hex digits: {}", String::from_utf8_lossy(pair)).into()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_human_bytes() {
+        assert_eq!(human_bytes(0), "0 B");
+        assert_eq!(human_bytes(1023), "1023 B");
+        assert_eq!(human_bytes(1024), "1.0 KiB");
+        assert_eq!(human_bytes(1536), "1.5 KiB");
+        assert_eq!(human_bytes(1048576), "1.0 MiB");
+    }
+
+    #[test]
+    fn test_escape_bytes() {
+        assert_eq!(escape_bytes(b"line\n\0end"), "line\\n\\x00end");
+        assert_eq!(escape_bytes(b"\xff\"quoted\""), "\\xff\\\"quoted\\\"");
+    }
+
+    #[test]
+    fn test_paint() {
+        assert_eq!(paint("IHDR", RED, true), "\x1b[31mIHDR\x1b[0m");
+        assert_eq!(paint("IHDR", RED, false), "IHDR");
+    }
+
+    #[test]
+    fn test_to_hex() {
+        assert_eq!(to_hex(b"Hello"), "48656c6c6f");
+        assert_eq!(to_hex(&[]), "");
+        assert_eq!(parse_hex(&to_hex(&[0, 15, 255])).unwrap(), [0, 15, 255]);
+    }
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(parse_hex("48656c6c6f").unwrap(), b"Hello");
+        assert_eq!(parse_hex("DEADbeef").unwrap(), [0xde, 0xad, 0xbe, 0xef]);
+        assert!(parse_hex("").unwrap().is_empty());
+        assert!(parse_hex("abc").is_err());
+        assert!(parse_hex("zz").is_err());
+        assert!(parse_hex("+1").is_err());
+    }
+}
@@ -1,10 +1,39 @@
 use std::{error, result};
 
+use crc::{Crc, Digest, CRC_32_ISO_HDLC};
+
 pub mod args;
 pub mod chunk;
 pub mod chunk_type;
 pub mod commands;
+pub mod format;
+pub mod log;
+pub mod metadata;
 pub mod png;
+pub mod repl;
+pub mod sink;
+pub mod text;
+
+#[cfg(test)]
+mod test_utils;
 
 pub type Error = Box<dyn error::Error>;
 pub type Result<T> = result::Result<T, Error>;
+
+static CRC_32: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+
+/// CRC-32 as used by PNG. A chunk's CRC covers its type and data bytes,
+/// not the length field.
+///
+/// ```
+/// // IEND has no data, so its CRC is just the CRC of the type.
+/// assert_eq!(pngme::png_crc(b"IEND"), 0xAE42_6082);
+/// ```
+pub fn png_crc(bytes: &[u8]) -> u32 {
+    CRC_32.checksum(bytes)
+}
+
+// 分段计算 CRC，用于边写边算的场景
+pub(crate) fn png_crc_digest() -> Digest<'static, u32> {
+    CRC_32.digest()
+}
@@ -0,0 +1,61 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
+};
+
+// 警告只写到 stderr，不会混进管道里的 stdout
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+// 每一步花了多久，也写到 stderr；是否输出由调用方根据 --timings 决定
+pub fn timing(step: &str, start: Instant) {
+    let message = format!("timing: {} took {:.2?}", step, start.elapsed());
+    eprintln!("{}", message);
+
+    #[cfg(test)]
+    TIMING_LINES.with(|lines| lines.borrow_mut().push(message));
+}
+
+// 操作结果之类的提示，和警告一样写到 stderr，--quiet 时不输出
+pub fn info(message: &str) {
+    if !QUIET.load(Ordering::Relaxed) {
+        eprintln!("{}", message);
+    }
+
+    #[cfg(test)]
+    INFO.with(|info| info.borrow_mut().push(message.to_string()));
+}
+
+pub fn warn(message: &str) {
+    if !QUIET.load(Ordering::Relaxed) {
+        eprintln!("warning: {}", message);
+    }
+
+    #[cfg(test)]
+    WARNINGS.with(|warnings| warnings.borrow_mut().push(message.to_string()));
+}
+
+#[cfg(test)]
+thread_local! {
+    static WARNINGS: std::cell::RefCell<Vec<String>> = const { std::cell::RefCell::new(vec![]) };
+    static INFO: std::cell::RefCell<Vec<String>> = const { std::cell::RefCell::new(vec![]) };
+    static TIMING_LINES: std::cell::RefCell<Vec<String>> = const { std::cell::RefCell::new(vec![]) };
+}
+
+#[cfg(test)]
+pub(crate) fn take_warnings() -> Vec<String> {
+    WARNINGS.with(|warnings| warnings.take())
+}
+
+#[cfg(test)]
+pub(crate) fn take_info() -> Vec<String> {
+    INFO.with(|info| info.take())
+}
+
+#[cfg(test)]
+pub(crate) fn take_timings() -> Vec<String> {
+    TIMING_LINES.with(|lines| lines.take())
+}
@@ -2,14 +2,41 @@ use clap::StructOpt;
 
 use pngme::{
     args::{Cli, Command},
-    commands, Result,
+    commands, log, Result,
 };
 
 fn main() -> Result<()> {
-    match &Cli::parse().command {
-        Command::Encode(args) => commands::encode(args),
+    let cli = Cli::parse();
+    log::set_quiet(cli.quiet);
+    let options = commands::Options {
+        recompute_crc: cli.recompute_crc,
+        timings: cli.timings,
+        no_inplace: cli.no_inplace,
+    };
+
+    match &cli.command {
+        Command::Encode(args) => commands::encode(args, &options),
         Command::Decode(args) => commands::decode(args),
-        Command::Remove(args) => commands::remove(args),
+        Command::Remove(args) => commands::remove(args, &options),
         Command::Print(args) => commands::print(args),
+        Command::Normalize(args) => commands::normalize(args, &options),
+        Command::Minify(args) => commands::minify(args, &options),
+        Command::Scan(args) => commands::scan(args),
+        Command::ExtractChunk(args) => commands::extract_chunk(args),
+        Command::InjectChunk(args) => commands::inject_chunk(args, &options),
+        Command::Interactive(args) => commands::interactive(args, &options),
+        Command::Stats(args) => commands::stats(args),
+        Command::Verify(args) => commands::verify(args),
+        Command::Metadata(args) => commands::metadata(args),
+        Command::Diff(args) => commands::diff(args),
+        Command::Grep(args) => commands::grep(args),
+        Command::Fix(args) => commands::fix(args, &options),
+        Command::Layout(args) => commands::layout(args),
+        Command::Merge(args) => commands::merge(args, &options),
+        Command::DumpText(args) => commands::dump_text(args),
+        Command::Dedup(args) => commands::dedup(args, &options),
+        Command::Checksum(args) => commands::checksum(args),
+        Command::Explode(args) => commands::explode(args),
+        Command::Implode(args) => commands::implode(args, &options),
     }
 }
@@ -0,0 +1,420 @@
+use crate::{chunk::Chunk, Result};
+
+fn expect_type(chunk: &Chunk, chunk_type: &[u8; 4]) -> Result<()> {
+    if chunk.chunk_type().bytes() != *chunk_type {
+        return Err(format!(
+            "expected {} chunk, found {}",
+            String::from_utf8_lossy(chunk_type),
+            chunk.chunk_type()
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+pub struct ImageHeader {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+    interlace: bool,
+}
+
+impl ImageHeader {
+    pub fn new(width: u32, height: u32, bit_depth: u8, color_type: u8, interlace: bool) -> Self {
+        ImageHeader {
+            width,
+            height,
+            bit_depth,
+            color_type,
+            interlace,
+        }
+    }
+
+    // IHDR 固定 13 字节：宽、高、位深、颜色类型、压缩、过滤、隔行
+    pub fn from_chunk(chunk: &Chunk) -> Result<ImageHeader> {
+        expect_type(chunk, b"IHDR")?;
+
+        let data = chunk.data();
+        if data.len() != 13 {
+            return Err("invalid image header length".into());
+        }
+
+        Ok(ImageHeader {
+            width: u32::from_be_bytes(data[0..4].try_into()?),
+            height: u32::from_be_bytes(data[4..8].try_into()?),
+            bit_depth: data[8],
+            color_type: data[9],
+            interlace: data[12] == 1,
+        })
+    }
+
+    // 压缩和过滤方法目前只定义了 0
+    pub fn to_bytes(&self) -> [u8; 13] {
+        let mut bytes = [0; 13];
+        bytes[0..4].copy_from_slice(&self.width.to_be_bytes());
+        bytes[4..8].copy_from_slice(&self.height.to_be_bytes());
+        bytes[8] = self.bit_depth;
+        bytes[9] = self.color_type;
+        bytes[12] = self.interlace as u8;
+        bytes
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn bit_depth(&self) -> u8 {
+        self.bit_depth
+    }
+
+    pub fn color_type(&self) -> u8 {
+        self.color_type
+    }
+
+    pub fn interlace(&self) -> bool {
+        self.interlace
+    }
+}
+
+pub struct Palette {
+    entries: Vec<[u8; 3]>,
+}
+
+impl Palette {
+    // PLTE 是 1 到 256 个 RGB 三元组
+    pub fn from_chunk(chunk: &Chunk) -> Result<Palette> {
+        expect_type(chunk, b"PLTE")?;
+
+        let data = chunk.data();
+        if data.is_empty() || !data.len().is_multiple_of(3) || data.len() / 3 > 256 {
+            return Err("invalid palette length".into());
+        }
+
+        let entries = data
+            .chunks_exact(3)
+            .map(|rgb| [rgb[0], rgb[1], rgb[2]])
+            .collect();
+
+        Ok(Palette { entries })
+    }
+
+    pub fn entries(&self) -> &[[u8; 3]] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Srgb {
+    Perceptual,
+    RelativeColorimetric,
+    Saturation,
+    AbsoluteColorimetric,
+}
+
+impl Srgb {
+    // sRGB 只有 1 字节的渲染意图
+    pub fn from_chunk(chunk: &Chunk) -> Result<Srgb> {
+        expect_type(chunk, b"sRGB")?;
+
+        match chunk.data() {
+            [0] => Ok(Srgb::Perceptual),
+            [1] => Ok(Srgb::RelativeColorimetric),
+            [2] => Ok(Srgb::Saturation),
+            [3] => Ok(Srgb::AbsoluteColorimetric),
+            _ => Err("invalid rendering intent".into()),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Srgb::Perceptual => "perceptual",
+            Srgb::RelativeColorimetric => "relative colorimetric",
+            Srgb::Saturation => "saturation",
+            Srgb::AbsoluteColorimetric => "absolute colorimetric",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bkgd {
+    PaletteIndex(u8),
+    Gray(u16),
+    Rgb(u16, u16, u16),
+}
+
+impl Bkgd {
+    // 布局取决于 IHDR 里的颜色类型
+    pub fn from_chunk(chunk: &Chunk, header: &ImageHeader) -> Result<Bkgd> {
+        expect_type(chunk, b"bKGD")?;
+
+        let sample = |i: usize| u16::from_be_bytes([chunk.data()[i], chunk.data()[i + 1]]);
+        match (header.color_type(), chunk.data().len()) {
+            (3, 1) => Ok(Bkgd::PaletteIndex(chunk.data()[0])),
+            (0 | 4, 2) => Ok(Bkgd::Gray(sample(0))),
+            (2 | 6, 6) => Ok(Bkgd::Rgb(sample(0), sample(2), sample(4))),
+            _ => Err("invalid background color".into()),
+        }
+    }
+}
+
+pub struct Histogram {
+    frequencies: Vec<u16>,
+}
+
+impl Histogram {
+    // hIST 是每个调色板条目 2 字节的频率，有 PLTE 时条目数必须一致
+    pub fn from_chunk(chunk: &Chunk, palette: Option<&Palette>) -> Result<Histogram> {
+        expect_type(chunk, b"hIST")?;
+
+        let data = chunk.data();
+        if !data.len().is_multiple_of(2) {
+            return Err("invalid histogram length".into());
+        }
+        let frequencies: Vec<_> = data
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+
+        if let Some(palette) = palette.filter(|palette| palette.len() != frequencies.len()) {
+            return Err(format!(
+                "histogram has {} entries but the palette has {}",
+                frequencies.len(),
+                palette.len()
+            )
+            .into());
+        }
+
+        Ok(Histogram { frequencies })
+    }
+
+    pub fn frequencies(&self) -> &[u16] {
+        &self.frequencies
+    }
+
+    pub fn into_vec(self) -> Vec<u16> {
+        self.frequencies
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaletteEntry {
+    pub red: u16,
+    pub green: u16,
+    pub blue: u16,
+    pub alpha: u16,
+    pub frequency: u16,
+}
+
+pub struct SuggestedPalette {
+    name: String,
+    sample_depth: u8,
+    entries: Vec<PaletteEntry>,
+}
+
+impl SuggestedPalette {
+    // sPLT：以 0 结尾的名字、1 字节采样深度，然后是 RGBA + 2 字节频率的条目
+    pub fn from_chunk(chunk: &Chunk) -> Result<SuggestedPalette> {
+        expect_type(chunk, b"sPLT")?;
+
+        let data = chunk.data();
+        let nul = data
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or("missing palette name terminator")?;
+        if nul == 0 || nul > 79 {
+            return Err("invalid palette name length".into());
+        }
+        let name = data[..nul].iter().map(|&b| b as char).collect();
+
+        let sample_depth = *data.get(nul + 1).ok_or("missing sample depth")?;
+        let sample_size = match sample_depth {
+            8 => 1,
+            16 => 2,
+            _ => return Err("invalid sample depth".into()),
+        };
+
+        let entries = &data[nul + 2..];
+        let entry_size = sample_size * 4 + 2;
+        if !entries.len().is_multiple_of(entry_size) {
+            return Err("inconsistent palette entry size".into());
+        }
+
+        let entries = entries
+            .chunks_exact(entry_size)
+            .map(|entry| {
+                let sample = |i: usize| match sample_size {
+                    1 => entry[i] as u16,
+                    _ => u16::from_be_bytes([entry[i * 2], entry[i * 2 + 1]]),
+                };
+                PaletteEntry {
+                    red: sample(0),
+                    green: sample(1),
+                    blue: sample(2),
+                    alpha: sample(3),
+                    frequency: u16::from_be_bytes([entry[entry_size - 2], entry[entry_size - 1]]),
+                }
+            })
+            .collect();
+
+        Ok(SuggestedPalette {
+            name,
+            sample_depth,
+            entries,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn sample_depth(&self) -> u8 {
+        self.sample_depth
+    }
+
+    pub fn entries(&self) -> &[PaletteEntry] {
+        &self.entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_utils;
+
+    #[test]
+    fn test_image_header() {
+        let chunk = test_utils::minimal_chunks().remove(0);
+        let header = ImageHeader::from_chunk(&chunk).unwrap();
+
+        assert_eq!((header.width(), header.height()), (1, 1));
+        assert_eq!(header.bit_depth(), 8);
+        assert_eq!(header.color_type(), 0);
+        assert!(!header.interlace());
+
+        let chunk = test_utils::chunk("IHDR", &[0; 12]);
+        assert!(ImageHeader::from_chunk(&chunk).is_err());
+    }
+
+    #[test]
+    fn test_image_header_to_bytes() {
+        let chunk = test_utils::minimal_chunks().remove(0);
+        let header = ImageHeader::from_chunk(&chunk).unwrap();
+        assert_eq!(header.to_bytes(), chunk.data());
+    }
+
+    #[test]
+    fn test_palette() {
+        let chunk = test_utils::chunk("PLTE", &[255, 0, 0, 0, 255, 0, 0, 0, 255]);
+        let palette = Palette::from_chunk(&chunk).unwrap();
+
+        assert_eq!(palette.len(), 3);
+        assert_eq!(palette.entries(), [[255, 0, 0], [0, 255, 0], [0, 0, 255]]);
+    }
+
+    #[test]
+    fn test_palette_invalid_length() {
+        let chunk = test_utils::chunk("PLTE", &[255, 0, 0, 0]);
+        assert!(Palette::from_chunk(&chunk).is_err());
+
+        let chunk = test_utils::chunk("PLTE", &[0; 257 * 3]);
+        assert!(Palette::from_chunk(&chunk).is_err());
+
+        let chunk = test_utils::chunk("tEXt", &[255, 0, 0]);
+        assert!(Palette::from_chunk(&chunk).is_err());
+    }
+
+    #[test]
+    fn test_srgb() {
+        let chunk = test_utils::chunk("sRGB", &[0]);
+        let srgb = Srgb::from_chunk(&chunk).unwrap();
+
+        assert_eq!(srgb, Srgb::Perceptual);
+        assert_eq!(srgb.name(), "perceptual");
+
+        assert!(Srgb::from_chunk(&test_utils::chunk("sRGB", &[4])).is_err());
+        assert!(Srgb::from_chunk(&test_utils::chunk("sRGB", &[0, 0])).is_err());
+    }
+
+    #[test]
+    fn test_bkgd() {
+        let gray = ImageHeader::from_chunk(&test_utils::minimal_chunks().remove(0)).unwrap();
+        let chunk = test_utils::chunk("bKGD", &[0x01, 0x02]);
+        assert_eq!(Bkgd::from_chunk(&chunk, &gray).unwrap(), Bkgd::Gray(0x0102));
+
+        let rgb = test_utils::chunk("IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 2, 0, 0, 0]);
+        let rgb = ImageHeader::from_chunk(&rgb).unwrap();
+        let chunk = test_utils::chunk("bKGD", &[0, 255, 0, 128, 0, 0]);
+        assert_eq!(
+            Bkgd::from_chunk(&chunk, &rgb).unwrap(),
+            Bkgd::Rgb(255, 128, 0)
+        );
+
+        assert!(Bkgd::from_chunk(&chunk, &gray).is_err());
+    }
+
+    #[test]
+    fn test_suggested_palette() {
+        let data = [
+            b"web\0".as_slice(),
+            &[8],
+            &[255, 0, 0, 255, 0, 10],
+            &[0, 0, 255, 128, 0, 2],
+        ]
+        .concat();
+        let palette = SuggestedPalette::from_chunk(&test_utils::chunk("sPLT", &data)).unwrap();
+
+        assert_eq!(palette.name(), "web");
+        assert_eq!(palette.sample_depth(), 8);
+        assert_eq!(
+            palette.entries()[1],
+            PaletteEntry {
+                red: 0,
+                green: 0,
+                blue: 255,
+                alpha: 128,
+                frequency: 2,
+            }
+        );
+
+        let data = [b"web\0".as_slice(), &[16], &[0; 10]].concat();
+        let palette = SuggestedPalette::from_chunk(&test_utils::chunk("sPLT", &data)).unwrap();
+        assert_eq!(palette.entries().len(), 1);
+
+        // 16 位深度但条目只有 8 位的长度
+        let data = [b"web\0".as_slice(), &[16], &[0; 6]].concat();
+        assert!(SuggestedPalette::from_chunk(&test_utils::chunk("sPLT", &data)).is_err());
+        assert!(SuggestedPalette::from_chunk(&test_utils::chunk("sPLT", b"web")).is_err());
+    }
+
+    #[test]
+    fn test_histogram() {
+        let chunk = test_utils::chunk("hIST", &[0, 1, 0, 2, 1, 0, 255, 255]);
+        let histogram = Histogram::from_chunk(&chunk, None).unwrap();
+        assert_eq!(histogram.frequencies(), [1, 2, 256, 65535]);
+
+        let palette = Palette::from_chunk(&test_utils::chunk("PLTE", &[0; 12])).unwrap();
+        let histogram = Histogram::from_chunk(&chunk, Some(&palette)).unwrap();
+        assert_eq!(histogram.into_vec(), [1, 2, 256, 65535]);
+
+        let palette = Palette::from_chunk(&test_utils::chunk("PLTE", &[0; 9])).unwrap();
+        assert!(Histogram::from_chunk(&chunk, Some(&palette)).is_err());
+
+        let chunk = test_utils::chunk("hIST", &[0, 1, 0]);
+        assert!(Histogram::from_chunk(&chunk, None).is_err());
+    }
+}
@@ -1,11 +1,20 @@
 use std::{
+    collections::{BTreeMap, BTreeSet},
     convert::TryFrom,
+    error,
     fmt::{Display, Formatter, Result as FmtResult},
-    io::{BufRead, BufReader, Read},
+    io::{self, BufRead, BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write},
 };
 
-use crate::{chunk::Chunk, Error, Result};
+use crate::{
+    chunk::{Chunk, ChunkRef},
+    chunk_type::ChunkType,
+    log,
+    metadata::ImageHeader,
+    Error, Result,
+};
 
+#[derive(Clone)]
 pub struct Png {
     header: [u8; 8],
     chunks: Vec<Chunk>,
@@ -13,6 +22,7 @@ pub struct Png {
 
 impl Png {
     pub const STANDARD_HEADER: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+    pub(crate) const NOT_PNG: &'static str = "not a PNG file (invalid signature)";
 
     pub fn from_chunks(chunks: Vec<Chunk>) -> Self {
         Self {
@@ -21,6 +31,31 @@ impl Png {
         }
     }
 
+    // 要求 IHDR 在最前、IEND 在最后；wrap 时把 IHDR 挪到最前并补上缺的 IEND
+    pub fn try_from_chunks(mut chunks: Vec<Chunk>, wrap: bool) -> Result<Self> {
+        if wrap {
+            let ihdr = chunks
+                .iter()
+                .position(|chunk| *chunk.chunk_type() == ChunkType::IHDR);
+            if let Some(index) = ihdr {
+                let ihdr = chunks.remove(index);
+                chunks.insert(0, ihdr);
+            }
+            if !chunks.last().is_some_and(Chunk::is_iend) {
+                chunks.push(Chunk::iend());
+            }
+        }
+
+        if chunks.first().map(Chunk::chunk_type) != Some(&ChunkType::IHDR) {
+            return Err("first chunk must be IHDR".into());
+        }
+        if !chunks.last().is_some_and(Chunk::is_iend) {
+            return Err("last chunk must be IEND".into());
+        }
+
+        Ok(Self::from_chunks(chunks))
+    }
+
     pub fn header(&self) -> &[u8; 8] {
         &self.header
     }
@@ -29,14 +64,185 @@ impl Png {
         &self.chunks
     }
 
+    pub fn chunk(&self, index: usize) -> Option<&Chunk> {
+        self.chunks.get(index)
+    }
+
     pub fn chunk_by_type(&self, chunk_type: &str) -> Option<&Chunk> {
         self.chunks
             .iter()
             .find(|chunk| chunk.chunk_type().to_string().as_str() == chunk_type)
     }
 
+    pub fn chunk_by_type_mut(&mut self, chunk_type: &str) -> Option<&mut Chunk> {
+        self.chunks
+            .iter_mut()
+            .find(|chunk| chunk.chunk_type().to_string().as_str() == chunk_type)
+    }
+
+    // 每个 chunk 在文件里的起始偏移
+    pub fn chunk_offsets(&self) -> Vec<usize> {
+        self.chunks
+            .iter()
+            .scan(self.header.len(), |offset, chunk| {
+                let start = *offset;
+                *offset += chunk.length() as usize + 12;
+                Some(start)
+            })
+            .collect()
+    }
+
+    pub fn position_of(&self, chunk_type: &str) -> Option<usize> {
+        let index = self
+            .chunks
+            .iter()
+            .position(|chunk| chunk.chunk_type().to_string() == chunk_type)?;
+        Some(self.chunk_offsets()[index])
+    }
+
+    // 8 字节签名，加上每个 chunk 的长度、类型、CRC 共 12 字节和数据
+    pub fn total_size(&self) -> usize {
+        self.header.len()
+            + self
+                .chunks
+                .iter()
+                .map(|chunk| chunk.length() as usize + 12)
+                .sum::<usize>()
+    }
+
+    // 同类型 chunk 的数据长度之和，不含 chunk 开销
+    pub fn data_size_of_type(&self, chunk_type: &str) -> usize {
+        self.chunks
+            .iter()
+            .filter(|chunk| chunk.chunk_type().bytes() == chunk_type.as_bytes())
+            .map(|chunk| chunk.length() as usize)
+            .sum()
+    }
+
+    // 签名加各 chunk 的大小和实际字节数对不上：已经读到 IEND 说明后面带了多余数据，否则是文件被截断
+    pub fn check_length(&self, actual: usize) -> Result<()> {
+        let parsed = self.total_size();
+        if parsed == actual {
+            return Ok(());
+        }
+
+        let reason = if self.chunks.last().is_some_and(Chunk::is_iend) {
+            "trailing data after IEND"
+        } else {
+            "truncated"
+        };
+        Err(format!(
+            "file is {} bytes but parsed chunks account for {} ({})",
+            actual, parsed, reason
+        )
+        .into())
+    }
+
+    pub fn stats(&self) -> Stats {
+        let mut bytes_by_type = BTreeMap::new();
+        let mut largest: Option<(String, usize)> = None;
+
+        for chunk in self.chunks.iter() {
+            let chunk_type = chunk.chunk_type().to_string();
+            let size = chunk.length() as usize + 12;
+
+            *bytes_by_type.entry(chunk_type.clone()).or_default() += size;
+            if largest.as_ref().is_none_or(|(_, largest)| size > *largest) {
+                largest = Some((chunk_type, size));
+            }
+        }
+
+        Stats {
+            total_size: self.total_size(),
+            chunk_count: self.chunks.len(),
+            bytes_by_type,
+            largest,
+        }
+    }
+
+    pub fn dimensions(&self) -> Result<(u32, u32)> {
+        let header = ImageHeader::from_chunk(self.chunk_by_type("IHDR").ok_or("missing IHDR")?)?;
+        Ok((header.width(), header.height()))
+    }
+
+    // 改尺寸之类的工具用：写回 IHDR 并重算 CRC
+    pub fn set_header(&mut self, header: ImageHeader) -> Result<()> {
+        if header.width() == 0 || header.height() == 0 {
+            return Err("image dimensions must be non-zero".into());
+        }
+
+        self.chunk_by_type_mut("IHDR")
+            .ok_or("missing IHDR")?
+            .set_data(header.to_bytes().to_vec());
+        Ok(())
+    }
+
+    pub fn chunk_type_set(&self) -> BTreeSet<String> {
+        self.chunks
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect()
+    }
+
+    // 非标准的辅助 chunk 很可能藏着数据
+    pub fn suspicious_chunks(&self) -> Vec<&Chunk> {
+        self.chunks
+            .iter()
+            .filter(|chunk| !chunk.chunk_type().is_critical() && !chunk.chunk_type().is_standard())
+            .collect()
+    }
+
+    // 插在第一个 IEND 前面；没有 IEND 就放到最后
     pub fn append_chunk(&mut self, chunk: Chunk) {
-        self.chunks.push(chunk)
+        let mut iend = self
+            .chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, chunk)| chunk.is_iend())
+            .map(|(index, _)| index);
+
+        match iend.next() {
+            Some(index) => {
+                if iend.next().is_some() {
+                    log::warn("multiple IEND chunks, appending before the first");
+                }
+                self.chunks.insert(index, chunk)
+            }
+            None => self.chunks.push(chunk),
+        }
+    }
+
+    // 把 other 里的辅助 chunk 都搬过来，返回搬过来的类型
+    pub fn merge_ancillary_from(&mut self, other: &Png, skip_existing: bool) -> Vec<String> {
+        let existing: Vec<_> = self.chunks.iter().map(|c| c.chunk_type().clone()).collect();
+
+        let mut merged = vec![];
+        for chunk in other.chunks() {
+            let chunk_type = chunk.chunk_type();
+            if chunk_type.is_critical() || skip_existing && existing.contains(chunk_type) {
+                continue;
+            }
+            merged.push(chunk_type.to_string());
+            self.append_chunk(chunk.clone());
+        }
+
+        merged
+    }
+
+    // 去掉类型和数据都相同的重复辅助 chunk，保留第一个，返回去掉的个数
+    pub fn dedup(&mut self) -> usize {
+        let before = self.chunks.len();
+        let mut kept: Vec<Chunk> = vec![];
+        for chunk in self.chunks.drain(..) {
+            let duplicate = !chunk.chunk_type().is_critical()
+                && kept.iter().any(|other| other.content_eq(&chunk));
+            if !duplicate {
+                kept.push(chunk);
+            }
+        }
+        self.chunks = kept;
+
+        before - self.chunks.len()
     }
 
     // 妙
@@ -79,17 +285,31 @@ impl Png {
         // 如果迭代器有多个 usize::MAX 不匹配的元素，此函数可能会出现恐慌。
     }
 
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        self.as_bytes_into(&mut buf);
+        buf
+    }
+
+    // 关键 chunk 也会被删掉，调用方可以从返回值里看到
+    pub fn remove_chunk_if<F: FnMut(&Chunk) -> bool>(&mut self, mut f: F) -> Vec<Chunk> {
+        let (removed, kept) = self.chunks.drain(..).partition(|chunk| f(chunk));
+        self.chunks = kept;
+        removed
+    }
+
     // 妙
     // 先处理每一个 chunk，把单个 chunk 变成 Vec<u8>
     // 然后把多个 chunk 转换成的 Vec<u8> 集合在一起变成迭代器
-    // 之后通过 chain 把 header 连起来完成最后的 Vec<u8>
-    pub fn as_bytes(&self) -> Vec<u8> {
+    // 之后通过 chain 把 header 连起来，写进调用方给的 buf
+    pub fn as_bytes_into(&self, buf: &mut Vec<u8>) {
         let chunks = self
             .chunks
             .iter()
             .flat_map(|chunk| chunk.as_bytes().into_iter());
 
-        self.header.iter().copied().chain(chunks).collect()
+        buf.clear();
+        buf.extend(self.header.iter().copied().chain(chunks));
 
         // fn flat_map<U, F>(self, f: F) -> FlatMap<Self, U, F>
         // where
@@ -105,27 +325,568 @@ impl Png {
         // Another way of thinking about flat_map(): map’s closure returns one item for each element, and flat_map()’s closure returns an iterator for each element.
         // 您可以将 flat_map(f) 视为映射的语义等价物，然后像 map(f).flatten() 中那样进行展平。
     }
+
+    // 每个 chunk 要写四次，不缓冲的话每次都是一次系统调用
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<()> {
+        let mut writer = BufWriter::new(writer);
+
+        writer.write_all(&self.header)?;
+        for chunk in &self.chunks {
+            chunk.write_to(&mut writer)?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    // 返回 CRC 被改正过的 chunk 类型
+    pub fn recompute_crcs(&mut self) -> Vec<String> {
+        self.chunks
+            .iter_mut()
+            .filter_map(|chunk| {
+                chunk
+                    .recompute_crc()
+                    .then(|| chunk.chunk_type().to_string())
+            })
+            .collect()
+    }
+
+    // 合并后的 IDAT 放在第一个 IDAT 原来的位置
+    pub fn merge_idat(&mut self) {
+        let is_idat = |chunk: &Chunk| *chunk.chunk_type() == ChunkType::IDAT;
+        if self.chunks.iter().filter(|chunk| is_idat(chunk)).count() < 2 {
+            return;
+        }
+
+        let index = self.chunks.iter().position(is_idat).unwrap();
+        let (idat, rest): (Vec<_>, Vec<_>) = self.chunks.drain(..).partition(is_idat);
+        let data = idat
+            .iter()
+            .flat_map(|chunk| chunk.data())
+            .copied()
+            .collect();
+
+        self.chunks = rest;
+        self.chunks
+            .insert(index, Chunk::new(idat[0].chunk_type().clone(), data));
+    }
+
+    // 按规范要求的顺序稳定排序，返回排序前后位置变了的 chunk 类型
+    pub fn normalize(&mut self) -> Vec<String> {
+        let ranks = Self::ordering_ranks(&self.chunks);
+        let mut ranked: Vec<_> = ranks
+            .into_iter()
+            .zip(self.chunks.drain(..).enumerate())
+            .collect();
+        ranked.sort_by_key(|(rank, _)| *rank);
+
+        let mut moved = vec![];
+        for (position, (_, (original, chunk))) in ranked.into_iter().enumerate() {
+            if position != original {
+                moved.push(chunk.chunk_type().to_string());
+            }
+            self.chunks.push(chunk);
+        }
+
+        moved
+    }
+
+    // 排名比前面某个 chunk 小，说明它出现在了规范不允许的位置
+    pub fn ordering_report(&self) -> Vec<(String, bool)> {
+        let mut max_rank = 0;
+        self.chunks
+            .iter()
+            .zip(Self::ordering_ranks(&self.chunks))
+            .map(|(chunk, rank)| {
+                let legal = rank >= max_rank;
+                max_rank = max_rank.max(rank);
+                (chunk.chunk_type().to_string(), legal)
+            })
+            .collect()
+    }
+
+    // 规范只允许出现一次的 chunk 重复时报错
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        for chunk_type in Self::SINGLE {
+            let count = self
+                .chunks
+                .iter()
+                .filter(|chunk| chunk.chunk_type().bytes() == chunk_type.as_bytes())
+                .count();
+            if count > 1 {
+                errors.push(format!("duplicate {} chunk ({} found)", chunk_type, count));
+            }
+        }
+
+        errors
+    }
 }
 
-impl TryFrom<&[u8]> for Png {
-    type Error = Error;
+impl Png {
+    const SINGLE: [&'static str; 15] = [
+        "IHDR", "PLTE", "IEND", "cHRM", "cICP", "gAMA", "iCCP", "sBIT", "sRGB", "bKGD", "hIST",
+        "tRNS", "eXIf", "pHYs", "tIME",
+    ];
+    const BEFORE_PLTE: [&'static str; 6] = ["cHRM", "gAMA", "iCCP", "sBIT", "sRGB", "cICP"];
+    const AFTER_PLTE: [&'static str; 3] = ["bKGD", "hIST", "tRNS"];
+    const BEFORE_IDAT: [&'static str; 3] = ["pHYs", "sPLT", "eXIf"];
 
-    fn try_from(value: &[u8]) -> Result<Self> {
-        let mut reader = BufReader::new(value);
+    // 有位置约束的 chunk 直接给出排名；
+    // 没有约束的 chunk 跟随它前面有约束 chunk 的最高排名，这样一个放错位置的 chunk 不会连累后面的，
+    // 但不能落在 IHDR 之前、IDAT 之间或 IEND 之后
+    fn ordering_ranks(chunks: &[Chunk]) -> Vec<u8> {
+        let mut highest = None;
+        chunks
+            .iter()
+            .map(|chunk| {
+                let chunk_type = chunk.chunk_type().to_string();
+                let fixed = match chunk_type.as_str() {
+                    "IHDR" => Some(0),
+                    "PLTE" => Some(20),
+                    "IDAT" => Some(40),
+                    "IEND" => Some(60),
+                    t if Self::BEFORE_PLTE.contains(&t) => Some(10),
+                    t if Self::AFTER_PLTE.contains(&t) => Some(30),
+                    _ => None,
+                };
+
+                if let Some(rank) = fixed {
+                    highest = highest.max(Some(rank));
+                    return rank;
+                }
+
+                let rank = match highest {
+                    None | Some(0) => 5,
+                    Some(40) => 50,
+                    Some(60) => 55,
+                    Some(rank) => rank,
+                };
+
+                if Self::BEFORE_IDAT.contains(&chunk_type.as_str()) {
+                    rank.min(35)
+                } else {
+                    rank
+                }
+            })
+            .collect()
+    }
+
+    fn read_header<R: Read>(reader: &mut R) -> Result<[u8; 8]> {
+        Self::read_signature(reader, &Self::STANDARD_HEADER)
+    }
+
+    fn read_signature<R: Read>(reader: &mut R, signature: &[u8; 8]) -> Result<[u8; 8]> {
         let mut header = [0; 8];
 
-        reader.read_exact(&mut header)?;
-        if header != Self::STANDARD_HEADER {
-            return Err("invalid png".into());
+        // 不足 8 字节的文件也当作签名不对
+        match reader.read_exact(&mut header) {
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Err(Self::NOT_PNG.into()),
+            result => result?,
+        }
+        if header != *signature {
+            return Err(Self::NOT_PNG.into());
+        }
+
+        Ok(header)
+    }
+
+    // 文件开头混进了 BOM 之类的杂字节时，在前 limit 字节里找签名
+    pub fn find_signature(bytes: &[u8], limit: usize) -> Option<usize> {
+        let end = bytes.len().min(limit + Self::STANDARD_HEADER.len());
+        bytes[..end]
+            .windows(Self::STANDARD_HEADER.len())
+            .position(|window| window == Self::STANDARD_HEADER)
+    }
+
+    // 只读签名和 IHDR，不经过 BufReader，后面的字节一个都不碰
+    pub fn read_header_only<R: Read>(mut reader: R) -> Result<ImageHeader> {
+        Self::read_header(&mut reader)?;
+        ImageHeader::from_chunk(&Chunk::read_chunk(&mut reader)?)
+    }
+
+    // 只读每个 chunk 的长度和类型，数据和 CRC 直接 seek 跳过，不校验也不分配
+    pub fn scan_types<R: Read + Seek>(mut reader: R) -> Result<Vec<(ChunkType, u32)>> {
+        Self::read_header(&mut reader)?;
+        let end = reader.seek(SeekFrom::End(0))?;
+        let mut offset = reader.seek(SeekFrom::Start(Self::STANDARD_HEADER.len() as u64))?;
+
+        let mut types = vec![];
+        while offset < end {
+            let mut buffer = [0; 8];
+            reader.read_exact(&mut buffer)?;
+            let length = u32::from_be_bytes(buffer[..4].try_into()?);
+            let chunk_type = ChunkType::try_from(<[u8; 4]>::try_from(&buffer[4..])?)?;
+
+            offset = reader.seek(SeekFrom::Current(length as i64 + 4))?;
+            if offset > end {
+                return Err(format!("truncated {} chunk", chunk_type).into());
+            }
+            types.push((chunk_type, length));
+        }
+
+        Ok(types)
+    }
+
+    pub fn read_from<R: Read>(reader: R) -> Result<Png> {
+        Self::read_from_with_options(reader, &ParseOptions::default())
+    }
+
+    // 有些衍生格式只改了签名，chunk 结构和 PNG 一样
+    pub fn read_from_with_signature<R: Read>(reader: R, signature: &[u8; 8]) -> Result<Png> {
+        let options = ParseOptions {
+            signature: *signature,
+            ..ParseOptions::default()
+        };
+        Self::read_from_with_options(reader, &options)
+    }
+
+    // 不可信的输入：总共最多读 max_total_bytes 字节，超出就报错
+    pub fn read_from_limited<R: Read>(reader: R, max_total_bytes: u64) -> Result<Png> {
+        let reader = LimitedReader {
+            inner: reader,
+            remaining: max_total_bytes,
+            limit: max_total_bytes,
+        };
+        Self::read_from(reader)
+    }
+
+    pub fn read_from_with_options<R: Read>(reader: R, options: &ParseOptions) -> Result<Png> {
+        let mut reader = BufReader::new(reader);
+        let header = Self::read_signature(&mut reader, &options.signature)?;
+
+        let mut chunks = vec![];
+        let mut offset = header.len();
+        let mut seen_iend = false;
+        while !reader.fill_buf()?.is_empty() {
+            if seen_iend {
+                match options.after_iend {
+                    AfterIend::Ignore => break,
+                    AfterIend::Error => {
+                        return Err(format!("data after IEND at offset {}", offset).into())
+                    }
+                    AfterIend::Capture => {}
+                }
+            }
+
+            if chunks.len() == options.max_chunks {
+                return Err(format!("more than {} chunks", options.max_chunks).into());
+            }
+
+            let chunk = if options.lenient_iend_crc {
+                Self::read_chunk_lenient_iend(&mut reader)
+            } else {
+                Chunk::read_chunk(&mut reader)
+            }
+            .map_err(|e| format!("{} at offset {}", e, offset))?;
+            offset += chunk.length() as usize + 12;
+            if !chunk.chunk_type().is_reserved_bit_valid() {
+                log::warn(&format!(
+                    "chunk {} has the reserved bit set",
+                    chunk.chunk_type()
+                ));
+            }
+            seen_iend |= chunk.is_iend();
+            chunks.push(chunk);
         }
 
+        Ok(Self { header, chunks })
+    }
+
+    // 有些工具会截掉零长度 IEND 末尾的 CRC
+    fn read_chunk_lenient_iend<R: Read>(reader: &mut R) -> Result<Chunk> {
+        let mut head = vec![];
+        reader.by_ref().take(12).read_to_end(&mut head)?;
+        if (8..12).contains(&head.len()) && head[..8] == Chunk::iend().as_bytes()[..8] {
+            return Ok(Chunk::iend());
+        }
+
+        Chunk::read_chunk(&mut head.as_slice().chain(reader))
+    }
+
+    // 不校验 CRC，坏 CRC 原样保留，配合 recompute_crcs 修复文件
+    pub fn read_unverified(bytes: &[u8]) -> Result<Png> {
+        let mut reader = BufReader::new(bytes);
+        let header = Self::read_header(&mut reader)?;
+
         let mut chunks = vec![];
         while !reader.fill_buf()?.is_empty() {
-            chunks.push(Chunk::read_chunk(&mut reader)?);
+            chunks.push(Chunk::read_chunk_unverified(
+                &mut reader,
+                Chunk::MAX_LENGTH,
+            )?);
         }
 
         Ok(Self { header, chunks })
     }
+
+    // 读一个处理一个，不保留已经处理过的 chunk
+    pub fn for_each_chunk<R: Read, F: FnMut(&Chunk) -> Result<()>>(
+        reader: R,
+        mut f: F,
+    ) -> Result<()> {
+        let mut reader = BufReader::new(reader);
+        Self::read_header(&mut reader)?;
+
+        while !reader.fill_buf()?.is_empty() {
+            f(&Chunk::read_chunk(&mut reader)?)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn chunk_refs(bytes: &[u8]) -> Result<Vec<ChunkRef<'_>>> {
+        let mut rest = bytes;
+        Self::read_header(&mut rest)?;
+
+        let mut chunks = vec![];
+        while !rest.is_empty() {
+            chunks.push(ChunkRef::read(&mut rest)?);
+        }
+
+        Ok(chunks)
+    }
+
+    // 一个流里可能拼接了多张 PNG，每张读到 IEND 为止
+    pub fn read_all<R: Read>(reader: R) -> Result<Vec<Png>> {
+        let mut reader = BufReader::new(reader);
+        let mut pngs = vec![];
+
+        while !reader.fill_buf()?.is_empty() {
+            let header = Self::read_header(&mut reader)?;
+            let mut chunks = vec![];
+            loop {
+                let chunk = Chunk::read_chunk(&mut reader)?;
+                let is_iend = chunk.is_iend();
+                chunks.push(chunk);
+                if is_iend {
+                    break;
+                }
+            }
+
+            pngs.push(Self { header, chunks });
+        }
+
+        Ok(pngs)
+    }
+
+    // 不在第一个错误处停下，收集每个出错 chunk 的序号和错误；签名不对时没有 Png
+    pub fn parse_diagnostics(bytes: &[u8]) -> (Option<Png>, Vec<(usize, Error)>) {
+        let Some(mut rest) = bytes.strip_prefix(&Self::STANDARD_HEADER[..]) else {
+            return (None, vec![(0, Self::NOT_PNG.into())]);
+        };
+
+        let mut chunks = vec![];
+        let mut errors = vec![];
+        for index in 0.. {
+            if rest.is_empty() {
+                break;
+            }
+
+            let total = rest
+                .get(..4)
+                .map(|length| u32::from_be_bytes(length.try_into().unwrap()) as usize)
+                .and_then(|length| length.checked_add(12))
+                .filter(|&total| total <= rest.len());
+            let Some(total) = total else {
+                errors.push((index, "truncated chunk".into()));
+                break;
+            };
+
+            match Chunk::try_from(&rest[..total]) {
+                Ok(chunk) => chunks.push(chunk),
+                Err(e) => errors.push((index, e)),
+            }
+            rest = &rest[total..];
+        }
+
+        let png = Self {
+            header: Self::STANDARD_HEADER,
+            chunks,
+        };
+        (Some(png), errors)
+    }
+
+    pub fn try_from_recover(bytes: &[u8]) -> (Png, Vec<RecoveryError>) {
+        let mut errors = vec![];
+        let mut header = Self::STANDARD_HEADER;
+        let mut offset = 0;
+
+        match bytes.get(..8) {
+            Some(signature) if signature == Self::STANDARD_HEADER => offset = 8,
+            Some(signature) => {
+                header.copy_from_slice(signature);
+                errors.push(RecoveryError::new(0, 8, Self::NOT_PNG));
+                offset = 8;
+            }
+            None => errors.push(RecoveryError::new(0, bytes.len(), "truncated png")),
+        }
+
+        // 用长度字段重新同步：坏 chunk 整块跳过，继续读后面的
+        let mut chunks = vec![];
+        while offset < bytes.len() {
+            let rest = &bytes[offset..];
+            if rest.len() < 12 {
+                errors.push(RecoveryError::new(offset, rest.len(), "truncated chunk"));
+                break;
+            }
+
+            let length = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+            let total = match length.checked_add(12) {
+                Some(total) if total <= rest.len() => total,
+                _ => {
+                    errors.push(RecoveryError::new(offset, rest.len(), "truncated chunk"));
+                    break;
+                }
+            };
+
+            match Chunk::try_from(&rest[..total]) {
+                Ok(chunk) => chunks.push(chunk),
+                Err(e) => errors.push(RecoveryError::new(offset, total, &e.to_string())),
+            }
+            offset += total;
+        }
+
+        (Self { header, chunks }, errors)
+    }
+}
+
+pub struct Stats {
+    total_size: usize,
+    chunk_count: usize,
+    bytes_by_type: BTreeMap<String, usize>,
+    largest: Option<(String, usize)>,
+}
+
+impl Stats {
+    pub fn total_size(&self) -> usize {
+        self.total_size
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunk_count
+    }
+
+    // 每种类型占用的字节数，包含 12 字节的 chunk 开销
+    pub fn bytes_by_type(&self) -> &BTreeMap<String, usize> {
+        &self.bytes_by_type
+    }
+
+    pub fn largest(&self) -> Option<(&str, usize)> {
+        self.largest
+            .as_ref()
+            .map(|(chunk_type, size)| (chunk_type.as_str(), *size))
+    }
+
+    pub fn percentage_of(&self, chunk_type: &str) -> f64 {
+        let bytes = self.bytes_by_type.get(chunk_type).copied().unwrap_or(0);
+        bytes as f64 * 100.0 / self.total_size as f64
+    }
+}
+
+struct LimitedReader<R> {
+    inner: R,
+    remaining: u64,
+    limit: u64,
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // 额度用完后再读一个字节，确认输入确实超出了限制
+        if self.remaining == 0 {
+            return match self.inner.read(&mut [0])? {
+                0 => Ok(0),
+                _ => Err(io::Error::other(format!(
+                    "input exceeds {} bytes",
+                    self.limit
+                ))),
+            };
+        }
+
+        let max = buf
+            .len()
+            .min(self.remaining.try_into().unwrap_or(usize::MAX));
+        let n = self.inner.read(&mut buf[..max])?;
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+pub struct ParseOptions {
+    pub signature: [u8; 8],
+    // 防止大量零长度 chunk 耗尽内存
+    pub max_chunks: usize,
+    pub after_iend: AfterIend,
+    // 文件在 IEND 的 CRC 里结束时照样接受，补上标准的 IEND
+    pub lenient_iend_crc: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            signature: Png::STANDARD_HEADER,
+            max_chunks: 100_000,
+            after_iend: AfterIend::Capture,
+            lenient_iend_crc: false,
+        }
+    }
+}
+
+// IEND 之后的内容：丢弃、报错，或者照常当作 chunk 读进来
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AfterIend {
+    Ignore,
+    Error,
+    Capture,
+}
+
+#[derive(Debug)]
+pub struct RecoveryError {
+    offset: usize,
+    length: usize,
+    message: String,
+}
+
+impl RecoveryError {
+    fn new(offset: usize, length: usize, message: &str) -> Self {
+        Self {
+            offset,
+            length,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for RecoveryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{} (skipped {} bytes at offset {})",
+            self.message, self.length, self.offset
+        )
+    }
+}
+
+impl error::Error for RecoveryError {}
+
+impl TryFrom<&[u8]> for Png {
+    type Error = Error;
+
+    fn try_from(value: &[u8]) -> Result<Self> {
+        Self::read_from(value)
+    }
 }
 
 impl Display for Png {
@@ -144,9 +905,9 @@ impl Display for Png {
 mod tests {
     use super::*;
 
-    use std::str::FromStr;
+    use std::io::Cursor;
 
-    use crate::chunk_type::ChunkType;
+    use crate::test_utils;
 
     #[test]
     fn test_from_chunks() {
@@ -174,67 +935,272 @@ mod tests {
         let chunk_bytes: Vec<_> = testing_chunks().iter().flat_map(Chunk::as_bytes).collect();
         let bytes: Vec<_> = [13, 80, 78, 71, 13, 10, 26, 10]
             .iter()
-            .chain(chunk_bytes.iter())
+            .chain(chunk_bytes.iter())
+            .copied()
+            .collect();
+
+        let png = Png::try_from(bytes.as_ref());
+        assert!(png.is_err());
+    }
+
+    #[test]
+    fn test_invalid_chunk() {
+        let mut chunk_bytes: Vec<_> = testing_chunks().iter().flat_map(Chunk::as_bytes).collect();
+
+        #[rustfmt::skip]
+        let mut bad_chunk = vec![
+            0, 0, 0, 5,         // Length
+            32, 117, 83, 116,   // Chunk Type (Bad)
+            65, 64, 65, 66, 67, // Data
+            1, 2, 3, 4, 5       // CRC (Bad)
+        ];
+
+        chunk_bytes.append(&mut bad_chunk);
+
+        let png = Png::try_from(chunk_bytes.as_ref());
+        assert!(png.is_err());
+    }
+
+    #[test]
+    fn test_reserved_bit_warning() {
+        let mut png = Png::try_from(test_utils::minimal_png().as_ref()).unwrap();
+        assert!(log::take_warnings().is_empty());
+
+        png.append_chunk(chunk_from_strings("Rust", "reserved").unwrap());
+        let png = Png::try_from(png.as_bytes().as_ref()).unwrap();
+
+        let warnings = log::take_warnings();
+        assert_eq!(png.chunks().len(), 4);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("Rust"));
+    }
+
+    #[test]
+    fn test_list_chunks() {
+        let png = testing_png();
+        let chunks = png.chunks();
+
+        assert_eq!(chunks.len(), 3);
+    }
+
+    #[test]
+    fn test_chunk_by_type() {
+        let png = testing_png();
+        let chunk = png.chunk_by_type("FrSt").unwrap();
+
+        assert_eq!(&chunk.chunk_type().to_string(), "FrSt");
+        assert_eq!(&chunk.data_as_string().unwrap(), "I am the first chunk");
+    }
+
+    #[test]
+    fn test_remove_chunk_if() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let removed = png.remove_chunk_if(|chunk| !chunk.chunk_type().is_critical());
+
+        let removed: Vec<_> = removed
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        let kept: Vec<_> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+
+        assert_eq!(removed, ["sRGB", "gAMA", "pHYs"]);
+        assert_eq!(kept, ["IHDR", "IDAT", "RuSt", "IEND"]);
+    }
+
+    #[test]
+    fn test_suspicious_chunks() {
+        let mut png = Png::try_from(test_utils::png_with_text("Title", "clean").as_ref()).unwrap();
+        assert!(png.suspicious_chunks().is_empty());
+
+        png.append_chunk(chunk_from_strings("ruSt", "hidden").unwrap());
+        let suspicious = png.suspicious_chunks();
+        assert_eq!(suspicious.len(), 1);
+        assert_eq!(&suspicious[0].chunk_type().to_string(), "ruSt");
+    }
+
+    #[test]
+    fn test_append_chunk() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+
+        let chunk = png.chunk_by_type("TeSt").unwrap();
+        assert_eq!(&chunk.chunk_type().to_string(), "TeSt");
+        assert_eq!(&chunk.data_as_string().unwrap(), "Message");
+    }
+
+    #[test]
+    fn test_append_chunk_before_iend() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+
+        let chunks = png.chunks();
+        assert_eq!(&chunks[chunks.len() - 2].chunk_type().to_string(), "TeSt");
+        assert_eq!(&chunks[chunks.len() - 1].chunk_type().to_string(), "IEND");
+        assert!(log::take_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_append_chunk_without_iend() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+
+        let last = png.chunks().last().unwrap();
+        assert_eq!(&last.chunk_type().to_string(), "TeSt");
+    }
+
+    #[test]
+    fn test_append_chunk_duplicate_iend() {
+        let mut png = testing_png();
+        png.append_chunk(Chunk::iend());
+        png.append_chunk(Chunk::iend());
+        assert!(log::take_warnings().is_empty());
+
+        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+        let types: Vec<_> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+
+        assert_eq!(types, ["FrSt", "miDl", "LASt", "TeSt", "IEND", "IEND"]);
+        assert_eq!(log::take_warnings().len(), 1);
+    }
+
+    #[test]
+    fn test_remove_chunk() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+        png.remove_chunk("TeSt").unwrap();
+
+        let chunk = png.chunk_by_type("TeSt");
+        assert!(chunk.is_none());
+    }
+
+    #[test]
+    fn test_try_from_recover() {
+        let mut chunks: Vec<_> = testing_chunks().iter().map(Chunk::as_bytes).collect();
+        let middle = chunks[1].len() - 1;
+        chunks[1][middle] ^= 0xff;
+
+        let bytes: Vec<_> = Png::STANDARD_HEADER
+            .iter()
             .copied()
+            .chain(chunks.concat())
             .collect();
+        assert!(Png::try_from(bytes.as_ref()).is_err());
 
-        let png = Png::try_from(bytes.as_ref());
-        assert!(png.is_err());
+        let (png, errors) = Png::try_from_recover(&bytes);
+        let types: Vec<_> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+
+        assert_eq!(types, ["FrSt", "LASt"]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].offset(), 8 + chunks[0].len());
+        assert_eq!(errors[0].length(), chunks[1].len());
     }
 
     #[test]
-    fn test_invalid_chunk() {
-        let mut chunk_bytes: Vec<_> = testing_chunks().iter().flat_map(Chunk::as_bytes).collect();
+    fn test_parse_diagnostics() {
+        let mut chunks: Vec<_> = testing_chunks().iter().map(Chunk::as_bytes).collect();
+        let last = chunks[0].len() - 1;
+        chunks[0][last] ^= 0xff;
+        chunks[2][6] = b'1';
+        let bytes = [&Png::STANDARD_HEADER[..], &chunks.concat()].concat();
 
-        #[rustfmt::skip]
-        let mut bad_chunk = vec![
-            0, 0, 0, 5,         // Length
-            32, 117, 83, 116,   // Chunk Type (Bad)
-            65, 64, 65, 66, 67, // Data
-            1, 2, 3, 4, 5       // CRC (Bad)
-        ];
+        let (png, errors) = Png::parse_diagnostics(&bytes);
+        assert_eq!(png.unwrap().chunks().len(), 1);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].0, 0);
+        assert!(errors[0].1.to_string().contains("crc mismatch"));
+        assert_eq!(errors[1].0, 2);
+        assert!(errors[1].1.to_string().contains("'1'"));
 
-        chunk_bytes.append(&mut bad_chunk);
+        let (png, errors) = Png::parse_diagnostics(&bytes[..bytes.len() - 1]);
+        assert_eq!(png.unwrap().chunks().len(), 1);
+        assert_eq!(errors.last().unwrap().0, 2);
 
-        let png = Png::try_from(chunk_bytes.as_ref());
-        assert!(png.is_err());
+        let (png, errors) = Png::parse_diagnostics(&bytes[1..]);
+        assert!(png.is_none());
+        assert_eq!(errors.len(), 1);
     }
 
     #[test]
-    fn test_list_chunks() {
-        let png = testing_png();
-        let chunks = png.chunks();
+    fn test_merge_idat() {
+        let mut png = Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "header").unwrap(),
+            chunk_from_strings("IDAT", "one ").unwrap(),
+            chunk_from_strings("IDAT", "two ").unwrap(),
+            chunk_from_strings("IDAT", "three").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ]);
+        png.merge_idat();
 
-        assert_eq!(chunks.len(), 3);
+        let types: Vec<_> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(types, ["IHDR", "IDAT", "IEND"]);
+        assert_eq!(png.chunks()[1].data(), b"one two three");
+
+        let png = Png::try_from(png.as_bytes().as_ref()).unwrap();
+        assert_eq!(png.chunks().len(), 3);
     }
 
     #[test]
-    fn test_chunk_by_type() {
-        let png = testing_png();
-        let chunk = png.chunk_by_type("FrSt").unwrap();
+    fn test_normalize() {
+        let mut png = Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "header").unwrap(),
+            chunk_from_strings("tEXt", "text").unwrap(),
+            chunk_from_strings("IDAT", "first").unwrap(),
+            chunk_from_strings("IDAT", "second").unwrap(),
+            chunk_from_strings("PLTE", "palette").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ]);
 
-        assert_eq!(&chunk.chunk_type().to_string(), "FrSt");
-        assert_eq!(&chunk.data_as_string().unwrap(), "I am the first chunk");
-    }
+        let moved = png.normalize();
+        let types: Vec<_> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
 
-    #[test]
-    fn test_append_chunk() {
-        let mut png = testing_png();
-        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+        assert_eq!(moved, ["PLTE", "IDAT", "IDAT"]);
+        assert_eq!(types, ["IHDR", "tEXt", "PLTE", "IDAT", "IDAT", "IEND"]);
+        assert_eq!(png.chunks()[3].data(), b"first");
 
-        let chunk = png.chunk_by_type("TeSt").unwrap();
-        assert_eq!(&chunk.chunk_type().to_string(), "TeSt");
-        assert_eq!(&chunk.data_as_string().unwrap(), "Message");
+        // IDAT 之后的 tEXt 本来就合法，留在原地
+        let mut png = Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "header").unwrap(),
+            chunk_from_strings("IDAT", "data").unwrap(),
+            chunk_from_strings("PLTE", "palette").unwrap(),
+            chunk_from_strings("tEXt", "text").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ]);
+
+        let moved = png.normalize();
+        let types: Vec<_> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+
+        assert_eq!(moved, ["PLTE", "IDAT"]);
+        assert_eq!(types, ["IHDR", "PLTE", "IDAT", "tEXt", "IEND"]);
     }
 
     #[test]
-    fn test_remove_chunk() {
-        let mut png = testing_png();
-        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
-        png.remove_chunk("TeSt").unwrap();
-
-        let chunk = png.chunk_by_type("TeSt");
-        assert!(chunk.is_none());
+    fn test_normalize_already_ordered() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert!(png.normalize().is_empty());
+        assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
     }
 
     // This is the raw bytes for a shrunken version of the `dice.png` image on Wikipedia.
@@ -499,6 +1465,561 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_as_bytes_into() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let mut buf = vec![];
+
+        testing_png().as_bytes_into(&mut buf);
+        png.as_bytes_into(&mut buf);
+        assert_eq!(buf, png.as_bytes());
+
+        png.as_bytes_into(&mut buf);
+        assert_eq!(buf, png.as_bytes());
+    }
+
+    #[test]
+    fn test_write_to() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let mut bytes = vec![];
+        png.write_to(&mut bytes).unwrap();
+
+        assert_eq!(bytes, PNG_FILE.to_vec());
+    }
+
+    #[test]
+    fn test_read_from_with_signature() {
+        let signature = *b"GAMEASET";
+        let bytes: Vec<u8> = signature
+            .iter()
+            .chain(PNG_FILE[8..].iter())
+            .copied()
+            .collect();
+
+        let png = Png::read_from_with_signature(bytes.as_slice(), &signature).unwrap();
+        assert_eq!(png.header(), &signature);
+        assert_eq!(png.chunks().len(), 7);
+        assert_eq!(png.as_bytes(), bytes);
+
+        assert!(Png::read_from(bytes.as_slice()).is_err());
+        assert!(Png::read_from_with_signature(&PNG_FILE[..], &signature).is_err());
+    }
+
+    #[test]
+    fn test_not_a_png() {
+        for bytes in [
+            &b"hello, this is a text file"[..],
+            b"\xff\xd8\xff\xe0 JFIF",
+            b"",
+            b"\x89PNG",
+        ] {
+            let err = Png::try_from(bytes).err().unwrap();
+            assert_eq!(err.to_string(), "not a PNG file (invalid signature)");
+        }
+    }
+
+    #[test]
+    fn test_data_size_of_type() {
+        let mut chunks = test_utils::minimal_chunks();
+        chunks.insert(2, test_utils::chunk("IDAT", &[0; 100]));
+        let first = chunks[1].length() as usize;
+        let png = Png::from_chunks(chunks);
+
+        assert_eq!(png.data_size_of_type("IDAT"), first + 100);
+        assert_eq!(png.data_size_of_type("IHDR"), 13);
+        assert_eq!(png.data_size_of_type("tEXt"), 0);
+    }
+
+    fn after_iend_png() -> Vec<u8> {
+        let mut chunks = test_utils::minimal_chunks();
+        chunks.push(test_utils::chunk("taIl", b"after"));
+        Png::from_chunks(chunks).as_bytes()
+    }
+
+    fn read_after_iend(bytes: &[u8], after_iend: AfterIend) -> Result<Png> {
+        let options = ParseOptions {
+            after_iend,
+            ..ParseOptions::default()
+        };
+        Png::read_from_with_options(bytes, &options)
+    }
+
+    #[test]
+    fn test_after_iend_ignore() {
+        let mut bytes = after_iend_png();
+        let png = read_after_iend(&bytes, AfterIend::Ignore).unwrap();
+        assert_eq!(png.chunks().len(), 3);
+        assert!(png.chunks().last().unwrap().is_iend());
+
+        // 不是 chunk 的垃圾数据也一并忽略
+        bytes.extend_from_slice(b"garbage");
+        assert!(read_after_iend(&bytes, AfterIend::Ignore).is_ok());
+    }
+
+    #[test]
+    fn test_after_iend_error() {
+        let bytes = after_iend_png();
+        let err = read_after_iend(&bytes, AfterIend::Error).err().unwrap();
+        assert!(err.to_string().starts_with("data after IEND"));
+
+        assert!(read_after_iend(&test_utils::minimal_png(), AfterIend::Error).is_ok());
+    }
+
+    #[test]
+    fn test_after_iend_capture() {
+        let bytes = after_iend_png();
+        let png = read_after_iend(&bytes, AfterIend::Capture).unwrap();
+        assert_eq!(png.chunks().len(), 4);
+        assert_eq!(png.chunks()[3].data(), b"after");
+    }
+
+    #[test]
+    fn test_read_from_limited() {
+        let png = Png::read_from_limited(&PNG_FILE[..], PNG_FILE.len() as u64).unwrap();
+        assert_eq!(png.chunks().len(), 7);
+
+        let err = Png::read_from_limited(&PNG_FILE[..], 4000).err().unwrap();
+        assert_eq!(err.to_string(), "input exceeds 4000 bytes at offset 83");
+    }
+
+    #[test]
+    fn test_read_from_limited_huge_length() {
+        // IHDR 之后声明了一个接近 2 GB 的 chunk，但流本身一直没完
+        let mut bytes = PNG_FILE[..33].to_vec();
+        bytes.extend_from_slice(&0x7fff_0000u32.to_be_bytes());
+        bytes.extend_from_slice(b"IDAT");
+        let stream = bytes.as_slice().chain(io::repeat(0));
+
+        let err = Png::read_from_limited(stream, 1 << 16).err().unwrap();
+        assert!(err.to_string().contains("input exceeds 65536 bytes"));
+    }
+
+    #[test]
+    fn test_max_chunks() {
+        let mut bytes = Png::STANDARD_HEADER.to_vec();
+        let chunk = chunk_from_strings("tiNy", "").unwrap().as_bytes();
+        for _ in 0..1000 {
+            bytes.extend_from_slice(&chunk);
+        }
+
+        let options = ParseOptions {
+            max_chunks: 999,
+            ..ParseOptions::default()
+        };
+        let err = Png::read_from_with_options(bytes.as_slice(), &options)
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "more than 999 chunks");
+
+        let options = ParseOptions {
+            max_chunks: 1000,
+            ..ParseOptions::default()
+        };
+        let png = Png::read_from_with_options(bytes.as_slice(), &options).unwrap();
+        assert_eq!(png.chunks().len(), 1000);
+    }
+
+    #[test]
+    fn test_lenient_iend_crc() {
+        let options = ParseOptions {
+            lenient_iend_crc: true,
+            ..ParseOptions::default()
+        };
+
+        for missing in 1..=4 {
+            let bytes = &PNG_FILE[..PNG_FILE.len() - missing];
+            assert!(Png::try_from(bytes).is_err());
+
+            let png = Png::read_from_with_options(bytes, &options).unwrap();
+            assert_eq!(png.chunks().len(), 7);
+            assert!(png.chunks().last().unwrap().is_iend());
+            assert_eq!(png.as_bytes(), PNG_FILE);
+        }
+
+        let png = Png::read_from_with_options(&PNG_FILE[..], &options).unwrap();
+        assert_eq!(png.as_bytes(), PNG_FILE);
+        assert!(Png::read_from_with_options(&PNG_FILE[..PNG_FILE.len() - 5], &options).is_err());
+    }
+
+    #[test]
+    fn test_clone() {
+        let png = testing_png();
+        let mut snapshot = png.clone();
+
+        let chunk = snapshot.chunk_by_type_mut("FrSt").unwrap();
+        chunk.set_data(b"edited".to_vec());
+        snapshot.append_chunk(chunk_from_strings("NeWw", "new").unwrap());
+
+        let chunk = png.chunk_by_type("FrSt").unwrap();
+        assert_eq!(chunk.data(), b"I am the first chunk");
+        assert_eq!(png.chunks().len(), 3);
+        assert_eq!(snapshot.chunks().len(), 4);
+    }
+
+    #[test]
+    fn test_crc_error_has_offset() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[4785] ^= 1;
+
+        let err = Png::try_from(bytes.as_ref()).err().unwrap().to_string();
+        assert!(err.starts_with("crc mismatch in RuSt chunk: declared "));
+        assert!(err.ends_with("at offset 4776"));
+    }
+
+    #[test]
+    fn test_read_header_only() {
+        let mut rest = &PNG_FILE[..];
+        let header = Png::read_header_only(&mut rest).unwrap();
+
+        assert_eq!((header.width(), header.height()), (50, 50));
+        assert_eq!(rest.len(), PNG_FILE.len() - 8 - 25);
+
+        assert!(Png::read_header_only(&PNG_FILE[8..]).is_err());
+    }
+
+    // 记录实际读了多少字节
+    struct CountingReader<R> {
+        inner: R,
+        read: usize,
+    }
+
+    impl<R: Read> Read for CountingReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            self.read += n;
+            Ok(n)
+        }
+    }
+
+    impl<R: Seek> Seek for CountingReader<R> {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn test_scan_types() {
+        let mut reader = CountingReader {
+            inner: Cursor::new(PNG_FILE),
+            read: 0,
+        };
+        let types = Png::scan_types(&mut reader).unwrap();
+
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let expected: Vec<_> = png
+            .chunks()
+            .iter()
+            .map(|chunk| (chunk.chunk_type().clone(), chunk.length()))
+            .collect();
+        assert_eq!(types, expected);
+        // 签名加上每个 chunk 的长度和类型，IDAT 的数据一个字节都没读
+        assert_eq!(reader.read, 8 + 8 * expected.len());
+
+        let truncated = Cursor::new(&PNG_FILE[..PNG_FILE.len() - 1]);
+        assert!(Png::scan_types(truncated).is_err());
+
+        // 最后一个 chunk 的数据被截断时，seek 会落在文件末尾之后
+        let mut bytes = Png::from_chunks(test_utils::minimal_chunks()).as_bytes();
+        bytes.extend_from_slice(&test_utils::chunk("ruSt", b"cut off here").as_bytes()[..14]);
+        let error = Png::scan_types(Cursor::new(bytes)).err().unwrap();
+        assert_eq!(error.to_string(), "truncated ruSt chunk");
+    }
+
+    #[test]
+    fn test_merge_ancillary_from() {
+        let mut png = Png::from_chunks(test_utils::minimal_chunks());
+        let mut other = Png::from_chunks(test_utils::minimal_chunks());
+        other.append_chunk(test_utils::chunk("ruSt", b"hey"));
+
+        assert_eq!(png.merge_ancillary_from(&other, false), ["ruSt"]);
+        assert_eq!(png.chunk_by_type("ruSt").unwrap().data(), b"hey");
+        assert_eq!(png.chunks().len(), 4);
+        assert!(png.chunks().last().unwrap().is_iend());
+
+        assert!(png.merge_ancillary_from(&other, true).is_empty());
+        assert_eq!(png.merge_ancillary_from(&other, false), ["ruSt"]);
+        assert_eq!(png.chunks().len(), 5);
+    }
+
+    #[test]
+    fn test_chunk_at_index() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+
+        assert_eq!(png.chunk(0).unwrap().chunk_type().to_string(), "IHDR");
+        assert!(png.chunk(6).unwrap().is_iend());
+        assert!(png.chunk(7).is_none());
+    }
+
+    #[test]
+    fn test_set_header() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(png.dimensions().unwrap(), (50, 50));
+
+        let header = ImageHeader::new(640, 480, 8, 6, false);
+        png.set_header(header).unwrap();
+        let mut png = Png::try_from(png.as_bytes().as_slice()).unwrap();
+        assert_eq!(png.dimensions().unwrap(), (640, 480));
+
+        let header = ImageHeader::new(0, 480, 8, 6, false);
+        assert!(png.set_header(header).is_err());
+        assert_eq!(png.dimensions().unwrap(), (640, 480));
+    }
+
+    #[test]
+    fn test_chunk_type_set() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let types = png.chunk_type_set();
+
+        assert!(["IHDR", "IDAT", "IEND"].iter().all(|t| types.contains(*t)));
+
+        let png = Png::from_chunks(test_utils::minimal_chunks());
+        assert_eq!(
+            Vec::from_iter(png.chunk_type_set()),
+            ["IDAT", "IEND", "IHDR"]
+        );
+    }
+
+    #[test]
+    fn test_dedup() {
+        let mut png = Png::from_chunks(test_utils::minimal_chunks());
+        png.append_chunk(test_utils::chunk("ruSt", b"hey"));
+        png.append_chunk(test_utils::chunk("ruSt", b"hey"));
+        png.append_chunk(test_utils::chunk("ruSt", b"other"));
+        png.append_chunk(test_utils::chunk("IDAT", &[]));
+        png.append_chunk(test_utils::chunk("IDAT", &[]));
+
+        assert_eq!(png.dedup(), 1);
+        let types: Vec<_> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(
+            types,
+            ["IHDR", "IDAT", "ruSt", "ruSt", "IDAT", "IDAT", "IEND"]
+        );
+        assert_eq!(png.dedup(), 0);
+    }
+
+    #[test]
+    fn test_ordering_report() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert!(png.ordering_report().iter().all(|(_, legal)| *legal));
+
+        let png = Png::from_chunks(vec![
+            test_utils::chunk("IHDR", &[0; 13]),
+            test_utils::chunk("IDAT", &[]),
+            test_utils::chunk("bKGD", &[0, 0]),
+            test_utils::chunk("PLTE", &[0, 0, 0]),
+            Chunk::iend(),
+        ]);
+        let report: Vec<_> = png
+            .ordering_report()
+            .into_iter()
+            .map(|(_, legal)| legal)
+            .collect();
+        assert_eq!(report, [true, true, false, false, true]);
+
+        // IDAT 之后的 tEXt、zTXt 是合法的，不受前面放错的 gAMA 影响
+        let png = Png::from_chunks(vec![
+            test_utils::chunk("IHDR", &[0; 13]),
+            test_utils::chunk("IDAT", &[]),
+            test_utils::chunk("gAMA", &[0; 4]),
+            test_utils::chunk("tEXt", b"a\0b"),
+            test_utils::chunk("zTXt", b"a\0\0"),
+            Chunk::iend(),
+        ]);
+        let report: Vec<_> = png
+            .ordering_report()
+            .into_iter()
+            .map(|(_, legal)| legal)
+            .collect();
+        assert_eq!(report, [true, true, false, true, true, true]);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Png::try_from(&PNG_FILE[..]).unwrap().validate().is_empty());
+
+        let mut chunks = test_utils::minimal_chunks();
+        chunks.insert(1, test_utils::minimal_chunks().remove(0));
+        let png = Png::from_chunks(chunks);
+
+        assert_eq!(png.validate(), ["duplicate IHDR chunk (2 found)"]);
+    }
+
+    #[test]
+    fn test_recompute_crcs() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[4785] ^= 1;
+        assert!(Png::try_from(bytes.as_ref()).is_err());
+
+        let mut png = Png::read_unverified(&bytes).unwrap();
+        assert_eq!(png.recompute_crcs(), ["RuSt"]);
+        assert!(png.recompute_crcs().is_empty());
+        assert!(Png::try_from(png.as_bytes().as_ref()).is_ok());
+    }
+
+    #[test]
+    fn test_find_signature() {
+        let bytes = [&[0xef, 0xbb, 0xbf][..], &PNG_FILE[..]].concat();
+        assert!(Png::try_from(bytes.as_slice()).is_err());
+
+        let offset = Png::find_signature(&bytes, 16).unwrap();
+        assert_eq!(offset, 3);
+        assert!(Png::try_from(&bytes[offset..]).is_ok());
+
+        assert_eq!(Png::find_signature(&bytes, 2), None);
+        assert_eq!(Png::find_signature(&PNG_FILE[..], 0), Some(0));
+    }
+
+    #[test]
+    fn test_try_from_chunks() {
+        let png = Png::try_from_chunks(test_utils::minimal_chunks(), false).unwrap();
+        assert_eq!(png.as_bytes(), test_utils::minimal_png());
+
+        let mut chunks = test_utils::minimal_chunks();
+        chunks.pop();
+        chunks.swap(0, 1);
+        assert!(Png::try_from_chunks(chunks.clone(), false).is_err());
+
+        let png = Png::try_from_chunks(chunks, true).unwrap();
+        assert_eq!(png.as_bytes(), test_utils::minimal_png());
+
+        let chunks = vec![test_utils::chunk("IDAT", &[])];
+        assert!(Png::try_from_chunks(chunks, true).is_err());
+    }
+
+    #[test]
+    fn test_position_of() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+
+        // 签名 + IHDR、sRGB、gAMA、pHYs 四个 chunk
+        let expected = 8 + (13 + 12) + (1 + 12) + (4 + 12) + (9 + 12);
+        assert_eq!(png.position_of("IDAT"), Some(expected));
+        assert_eq!(&PNG_FILE[expected + 4..expected + 8], b"IDAT");
+        assert_eq!(png.position_of("IHDR"), Some(8));
+        assert_eq!(png.position_of("tEXt"), None);
+    }
+
+    #[test]
+    fn test_chunk_offsets() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let offsets = png.chunk_offsets();
+        assert_eq!(offsets, [8, 33, 46, 62, 83, 4776, 4791]);
+
+        let mut reader = Cursor::new(&PNG_FILE[..]);
+        let ihdr = Chunk::read_at(&mut reader, offsets[0] as u64).unwrap();
+        assert_eq!(ihdr.chunk_type().to_string(), "IHDR");
+        assert_eq!(ihdr.as_bytes(), png.chunks()[0].as_bytes());
+
+        let rust = Chunk::read_at(&mut reader, offsets[5] as u64).unwrap();
+        assert_eq!(rust.data(), b"hey");
+        assert!(Chunk::read_at(&mut reader, 9).is_err());
+    }
+
+    #[test]
+    fn test_total_size() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(png.total_size(), PNG_FILE.len());
+    }
+
+    #[test]
+    fn test_check_length() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert!(png.check_length(PNG_FILE.len()).is_ok());
+
+        let truncated = &PNG_FILE[..PNG_FILE.len() - 5];
+        let (png, _) = Png::try_from_recover(truncated);
+        let err = png.check_length(truncated.len()).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "file is 4798 bytes but parsed chunks account for 4791 (truncated)"
+        );
+
+        let trailing = [&PNG_FILE[..], b"junk"].concat();
+        let (png, _) = Png::try_from_recover(&trailing);
+        let err = png.check_length(trailing.len()).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "file is 4807 bytes but parsed chunks account for 4803 (trailing data after IEND)"
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = Png::try_from(&PNG_FILE[..]).unwrap().stats();
+
+        assert_eq!(stats.total_size(), 4803);
+        assert_eq!(stats.chunk_count(), 7);
+        assert_eq!(stats.bytes_by_type()["IDAT"], 4681 + 12);
+        assert_eq!(stats.largest(), Some(("IDAT", 4693)));
+        assert!((stats.percentage_of("IDAT") - 97.71).abs() < 0.01);
+        assert_eq!(stats.percentage_of("PLTE"), 0.0);
+    }
+
+    #[test]
+    fn test_for_each_chunk() {
+        let mut idat = 0;
+        let mut total = 0;
+        Png::for_each_chunk(&PNG_FILE[..], |chunk| {
+            if chunk.chunk_type().bytes() == *b"IDAT" {
+                idat += 1;
+            }
+            total += 1;
+            Ok(())
+        })
+        .unwrap();
+
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(idat, 1);
+        assert_eq!(total, png.chunks().len());
+    }
+
+    #[test]
+    fn test_for_each_chunk_stops_on_error() {
+        let mut seen = 0;
+        let result = Png::for_each_chunk(&PNG_FILE[..], |_| {
+            seen += 1;
+            Err("stop".into())
+        });
+
+        assert!(result.is_err());
+        assert_eq!(seen, 1);
+    }
+
+    #[test]
+    fn test_chunk_refs_borrow_source() {
+        let bytes = PNG_FILE.to_vec();
+        let chunks = Png::chunk_refs(&bytes).unwrap();
+        let source = bytes.as_ptr_range();
+
+        assert_eq!(chunks.len(), 7);
+        for chunk in &chunks {
+            let data = chunk.data().as_ptr_range();
+            assert!(source.start <= data.start && data.end <= source.end);
+        }
+
+        let owned: Vec<_> = chunks.iter().map(ChunkRef::to_owned).collect();
+        assert_eq!(Png::from_chunks(owned).as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_read_all() {
+        let bytes = [&PNG_FILE[..], &test_utils::minimal_png()].concat();
+        let pngs = Png::read_all(bytes.as_slice()).unwrap();
+
+        assert_eq!(pngs.len(), 2);
+        assert_eq!(pngs[0].as_bytes(), PNG_FILE.to_vec());
+        assert_eq!(pngs[1].as_bytes(), test_utils::minimal_png());
+    }
+
+    #[test]
+    fn test_read_all_truncated() {
+        let bytes = [&PNG_FILE[..], &PNG_FILE[..100]].concat();
+        assert!(Png::read_all(bytes.as_slice()).is_err());
+    }
+
     #[test]
     fn test_png_trait_impls() {
         let chunk_bytes: Vec<_> = testing_chunks().iter().flat_map(Chunk::as_bytes).collect();
@@ -528,9 +2049,6 @@ mod tests {
     }
 
     fn chunk_from_strings(chunk_type: &str, data: &str) -> Result<Chunk> {
-        let chunk_type = ChunkType::from_str(chunk_type)?;
-        let data = data.bytes().collect();
-
-        Ok(Chunk::new(chunk_type, data))
+        Chunk::from_strings(chunk_type, data)
     }
 }
@@ -0,0 +1,168 @@
+use std::io::{BufRead, Write};
+
+use crate::{
+    chunk::Chunk,
+    commands::{self, Options},
+    png::Png,
+    Result,
+};
+
+const HELP: &str =
+    "commands: list, print <type>, add <type> <message>, remove <type>, save [path], quit";
+
+// 所有修改都只作用于内存中的 png，直到 save 才写文件
+pub fn run<R: BufRead, W: Write>(
+    png: &mut Png,
+    path: &str,
+    options: &Options,
+    input: R,
+    mut output: W,
+) -> Result<()> {
+    write!(output, "> ")?;
+    output.flush()?;
+
+    for line in input.lines() {
+        let line = line?;
+        let mut words = line.trim().splitn(3, char::is_whitespace);
+
+        let result = match (words.next(), words.next(), words.next()) {
+            (None | Some(""), _, _) => Ok(()),
+            (Some("quit" | "exit"), _, _) => break,
+            (Some("help"), _, _) => writeln!(output, "{}", HELP).map_err(Into::into),
+            (Some("list"), _, _) => list(png, &mut output),
+            (Some("print"), Some(chunk_type), _) => print(png, chunk_type, &mut output),
+            (Some("add"), Some(chunk_type), message) => {
+                add(png, chunk_type, message.unwrap_or_default())
+            }
+            (Some("remove"), Some(chunk_type), _) => png.remove_chunk(chunk_type).map(|_| ()),
+            (Some("save"), target, _) => save(png, target.unwrap_or(path), options, &mut output),
+            _ => writeln!(output, "unknown command, {}", HELP).map_err(Into::into),
+        };
+
+        if let Err(e) = result {
+            writeln!(output, "error: {}", e)?;
+        }
+
+        write!(output, "> ")?;
+        output.flush()?;
+    }
+
+    writeln!(output)?;
+    Ok(())
+}
+
+fn list<W: Write>(png: &Png, output: &mut W) -> Result<()> {
+    for (index, chunk) in png.chunks().iter().enumerate() {
+        writeln!(
+            output,
+            "{:>3} {} {}",
+            index,
+            chunk.chunk_type(),
+            chunk.length()
+        )?;
+    }
+
+    Ok(())
+}
+
+fn print<W: Write>(png: &Png, chunk_type: &str, output: &mut W) -> Result<()> {
+    let chunk = png
+        .chunk_by_type(chunk_type)
+        .ok_or("non-existent chunk type")?;
+    writeln!(output, "{}", String::from_utf8_lossy(chunk.data()))?;
+
+    Ok(())
+}
+
+// 和 encode 一样，不允许往 IHDR、IDAT、IEND 这类关键 chunk 里写
+fn add(png: &mut Png, chunk_type: &str, message: &str) -> Result<()> {
+    let chunk = Chunk::from_strings(chunk_type, message)?;
+    if chunk.chunk_type().is_critical() {
+        return Err(format!(
+            "{} is a critical chunk type, writing to it may corrupt the image",
+            chunk_type
+        )
+        .into());
+    }
+    png.append_chunk(chunk);
+
+    Ok(())
+}
+
+fn save<W: Write>(png: &mut Png, path: &str, options: &Options, output: &mut W) -> Result<()> {
+    commands::write_png(png, path, options)?;
+    writeln!(output, "saved {}", path)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{env, fs, process};
+
+    use crate::test_utils;
+
+    #[test]
+    fn test_repl_script() {
+        let out_path = env::temp_dir().join(format!("pngme_{}_repl_out.png", process::id()));
+        let out_path = out_path.to_string_lossy().into_owned();
+        let script = format!(
+            "list\nadd ruSt hello there\nprint ruSt\nremove NoNe\nsave {}\n",
+            out_path
+        );
+
+        let mut png = Png::try_from(test_utils::minimal_png().as_ref()).unwrap();
+        let mut output = vec![];
+        run(
+            &mut png,
+            "unused.png",
+            &Options::default(),
+            script.as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("IHDR 13"));
+        assert!(output.contains("hello there\n"));
+        assert!(output.contains("error: non-existent chunk"));
+        assert!(output.contains(&format!("saved {}", out_path)));
+
+        let saved = Png::try_from(fs::read(&out_path).unwrap().as_ref()).unwrap();
+        assert_eq!(saved.chunk_by_type("ruSt").unwrap().data(), b"hello there");
+        assert!(saved.chunks().last().unwrap().is_iend());
+
+        fs::remove_file(out_path).unwrap();
+    }
+
+    #[test]
+    fn test_repl_list_and_save_default_path() {
+        let path = env::temp_dir().join(format!("pngme_{}_repl_default.png", process::id()));
+        let path = path.to_string_lossy().into_owned();
+
+        let mut png = Png::try_from(test_utils::minimal_png().as_ref()).unwrap();
+        let mut output = vec![];
+        run(
+            &mut png,
+            &path,
+            &Options::default(),
+            "add IEND x\nadd IDAT x\nlist\nsave\n".as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("error: IEND is a critical chunk type"));
+        assert!(output.contains("error: IDAT is a critical chunk type"));
+        // 两次 add 都被拒绝，list 只列出原来的三个 chunk
+        let idat_length = png.chunks()[1].length();
+        assert!(output.contains(&format!(
+            "  0 IHDR 13\n  1 IDAT {}\n  2 IEND 0\n> ",
+            idat_length
+        )));
+        assert_eq!(fs::read(&path).unwrap(), test_utils::minimal_png());
+        fs::remove_file(path).unwrap();
+    }
+}
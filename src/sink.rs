@@ -0,0 +1,54 @@
+use std::{fs::File, io::Stdout};
+
+use crate::{png::Png, Result};
+
+// 写 png 的目的地：文件、标准输出或内存
+pub trait PngSink {
+    fn write_png(&mut self, png: &Png) -> Result<()>;
+}
+
+impl PngSink for File {
+    fn write_png(&mut self, png: &Png) -> Result<()> {
+        png.write_to(self)
+    }
+}
+
+impl PngSink for Stdout {
+    fn write_png(&mut self, png: &Png) -> Result<()> {
+        png.write_to(self.lock())
+    }
+}
+
+impl PngSink for Vec<u8> {
+    fn write_png(&mut self, png: &Png) -> Result<()> {
+        png.write_to(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_utils;
+
+    #[test]
+    fn test_vec_sink() {
+        let png = Png::try_from(test_utils::minimal_png().as_ref()).unwrap();
+        let mut sink = vec![];
+        sink.write_png(&png).unwrap();
+
+        assert_eq!(sink, test_utils::minimal_png());
+        let round_trip = Png::try_from(sink.as_ref()).unwrap();
+        assert_eq!(round_trip.as_bytes(), png.as_bytes());
+    }
+
+    #[test]
+    fn test_vec_sink_appends() {
+        let png = Png::try_from(test_utils::minimal_png().as_ref()).unwrap();
+        let mut sink = b"prefix".to_vec();
+        sink.write_png(&png).unwrap();
+
+        assert_eq!(&sink[..6], b"prefix");
+        assert_eq!(&sink[6..], test_utils::minimal_png());
+    }
+}
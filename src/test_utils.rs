@@ -0,0 +1,65 @@
+use std::{io::Write, str::FromStr};
+
+use flate2::{write::ZlibEncoder, Compression};
+
+use crate::{chunk::Chunk, chunk_type::ChunkType, png::Png};
+
+pub(crate) fn chunk(chunk_type: &str, data: &[u8]) -> Chunk {
+    Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.to_vec())
+}
+
+// 1x1 灰度图：宽高各 1，位深 8，颜色类型 0，其余字段为 0
+pub(crate) fn minimal_chunks() -> Vec<Chunk> {
+    let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+    encoder.write_all(&[0, 0]).unwrap();
+    let idat = encoder.finish().unwrap();
+
+    vec![
+        chunk("IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0]),
+        chunk("IDAT", &idat),
+        Chunk::iend(),
+    ]
+}
+
+pub(crate) fn minimal_png() -> Vec<u8> {
+    Png::from_chunks(minimal_chunks()).as_bytes()
+}
+
+pub(crate) fn png_with_text(keyword: &str, value: &str) -> Vec<u8> {
+    let mut chunks = minimal_chunks();
+    let text = [keyword.as_bytes(), &[0], value.as_bytes()].concat();
+    chunks.insert(chunks.len() - 1, chunk("tEXt", &text));
+
+    Png::from_chunks(chunks).as_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimal_png() {
+        let png = Png::try_from(minimal_png().as_ref()).unwrap();
+        let types: Vec<_> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+
+        assert_eq!(types, ["IHDR", "IDAT", "IEND"]);
+    }
+
+    #[test]
+    fn test_png_with_text() {
+        let png = Png::try_from(png_with_text("Comment", "hello").as_ref()).unwrap();
+        let chunk = png.chunk_by_type("tEXt").unwrap();
+
+        assert_eq!(chunk.data(), b"Comment\0hello");
+        assert_eq!(png.chunks().len(), 4);
+    }
+
+    #[test]
+    fn test_fixtures_are_deterministic() {
+        assert_eq!(minimal_png(), minimal_png());
+    }
+}
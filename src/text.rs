@@ -0,0 +1,233 @@
+use std::{
+    convert::TryFrom,
+    io::{Read, Write},
+    str::FromStr,
+};
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+
+use crate::{chunk::Chunk, chunk_type::ChunkType, png::Png, Error, Result};
+
+pub struct InternationalText {
+    keyword: String,
+    language: String,
+    translated_keyword: String,
+    text: String,
+    compressed: bool,
+}
+
+impl InternationalText {
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn translated_keyword(&self) -> &str {
+        &self.translated_keyword
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.compressed
+    }
+}
+
+// keyword\0 compression_flag compression_method language\0 translated_keyword\0 text
+pub fn itxt(
+    keyword: &str,
+    lang: &str,
+    translated: &str,
+    text: &str,
+    compress: bool,
+) -> Result<Chunk> {
+    if keyword.is_empty() || keyword.len() > 79 || keyword.contains('\0') {
+        return Err("invalid keyword".into());
+    }
+    if lang.contains('\0') || translated.contains('\0') {
+        return Err("invalid itxt field".into());
+    }
+
+    let text = if compress {
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        encoder.write_all(text.as_bytes())?;
+        encoder.finish()?
+    } else {
+        text.as_bytes().to_vec()
+    };
+
+    let data: Vec<_> = keyword
+        .bytes()
+        .chain([0, compress as u8, 0])
+        .chain(lang.bytes())
+        .chain([0])
+        .chain(translated.bytes())
+        .chain([0])
+        .chain(text)
+        .collect();
+
+    Ok(Chunk::new(ChunkType::from_str("iTXt")?, data))
+}
+
+impl TryFrom<&Chunk> for InternationalText {
+    type Error = Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        if chunk.chunk_type().bytes() != *b"iTXt" {
+            return Err("not an itxt chunk".into());
+        }
+
+        let mut fields = chunk.data().splitn(2, |&b| b == 0);
+        let keyword = fields.next().unwrap_or_default();
+        let rest = fields.next().ok_or("invalid itxt chunk")?;
+
+        let (flag, method, rest) = match rest {
+            [flag, method, rest @ ..] => (*flag, *method, rest),
+            _ => return Err("invalid itxt chunk".into()),
+        };
+
+        let mut fields = rest.splitn(3, |&b| b == 0);
+        let language = fields.next().unwrap_or_default();
+        let translated_keyword = fields.next().ok_or("invalid itxt chunk")?;
+        let text = fields.next().ok_or("invalid itxt chunk")?;
+
+        let text = match (flag, method) {
+            (0, _) => text.to_vec(),
+            (1, 0) => {
+                let mut decoded = vec![];
+                ZlibDecoder::new(text).read_to_end(&mut decoded)?;
+                decoded
+            }
+            _ => return Err("unsupported itxt compression".into()),
+        };
+
+        Ok(Self {
+            keyword: String::from_utf8(keyword.to_vec())?,
+            language: String::from_utf8(language.to_vec())?,
+            translated_keyword: String::from_utf8(translated_keyword.to_vec())?,
+            text: String::from_utf8(text)?,
+            compressed: flag == 1,
+        })
+    }
+}
+
+fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+// tEXt：keyword\0 text，都是 Latin-1
+fn plain_text(chunk: &Chunk) -> Result<(String, String)> {
+    let mut fields = chunk.data().splitn(2, |&b| b == 0);
+    let keyword = fields.next().unwrap_or_default();
+    let text = fields.next().ok_or("invalid text chunk")?;
+
+    Ok((latin1(keyword), latin1(text)))
+}
+
+// zTXt：keyword\0 compression_method compressed_text
+fn compressed_text(chunk: &Chunk) -> Result<(String, String)> {
+    let mut fields = chunk.data().splitn(2, |&b| b == 0);
+    let keyword = fields.next().unwrap_or_default();
+    let text = match fields.next().ok_or("invalid ztxt chunk")? {
+        [0, text @ ..] => text,
+        _ => return Err("unsupported ztxt compression".into()),
+    };
+
+    let mut decoded = vec![];
+    ZlibDecoder::new(text).read_to_end(&mut decoded)?;
+    Ok((latin1(keyword), latin1(&decoded)))
+}
+
+// 所有文本 chunk 的 (keyword, text)；自定义的辅助 chunk 只要是 UTF-8 就以类型作为 keyword
+pub fn extract_all_text(png: &Png) -> Vec<(String, String)> {
+    png.chunks()
+        .iter()
+        .filter_map(|chunk| {
+            let chunk_type = chunk.chunk_type();
+            match &chunk_type.bytes() {
+                b"tEXt" => plain_text(chunk).ok(),
+                b"zTXt" => compressed_text(chunk).ok(),
+                b"iTXt" => InternationalText::try_from(chunk)
+                    .ok()
+                    .map(|text| (text.keyword, text.text)),
+                _ if !chunk_type.is_critical() && !chunk_type.is_standard() => chunk
+                    .data_as_string()
+                    .ok()
+                    .map(|text| (chunk_type.to_string(), text)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_utils;
+
+    #[test]
+    fn test_itxt_round_trip() {
+        let chunk = itxt("Title", "zh-CN", "标题", "你好，世界", false).unwrap();
+        let text = InternationalText::try_from(&chunk).unwrap();
+
+        assert_eq!(&chunk.chunk_type().to_string(), "iTXt");
+        assert_eq!(text.keyword(), "Title");
+        assert_eq!(text.language(), "zh-CN");
+        assert_eq!(text.translated_keyword(), "标题");
+        assert_eq!(text.text(), "你好，世界");
+        assert!(!text.is_compressed());
+    }
+
+    #[test]
+    fn test_itxt_compressed_round_trip() {
+        let message = "Grüße aus dem Süden! ".repeat(20);
+        let chunk = itxt("Comment", "de", "Kommentar", &message, true).unwrap();
+        let text = InternationalText::try_from(&chunk).unwrap();
+
+        assert!((chunk.length() as usize) < message.len());
+        assert_eq!(text.text(), message);
+        assert!(text.is_compressed());
+    }
+
+    #[test]
+    fn test_itxt_invalid_keyword() {
+        assert!(itxt("", "", "", "text", false).is_err());
+        assert!(itxt(&"k".repeat(80), "", "", "text", false).is_err());
+    }
+
+    #[test]
+    fn test_extract_all_text() {
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        encoder.write_all(b"squeezed").unwrap();
+        let ztxt = [b"Comment\0\0".as_slice(), &encoder.finish().unwrap()].concat();
+
+        let png = test_utils::png_with_text("Title", "hello");
+        let mut png = Png::try_from(png.as_ref()).unwrap();
+        png.append_chunk(test_utils::chunk("tEXt", b"Place\0caf\xe9"));
+        png.append_chunk(test_utils::chunk("zTXt", &ztxt));
+        png.append_chunk(itxt("Author", "", "", "你好", true).unwrap());
+        png.append_chunk(test_utils::chunk("ruSt", b"secret"));
+        png.append_chunk(test_utils::chunk("raWr", &[0xff, 0xfe]));
+
+        let text: Vec<_> = extract_all_text(&png)
+            .into_iter()
+            .map(|(keyword, text)| format!("{}:{}", keyword, text))
+            .collect();
+        assert_eq!(
+            text,
+            [
+                "Title:hello",
+                "Place:café",
+                "Comment:squeezed",
+                "Author:你好",
+                "ruSt:secret"
+            ]
+        );
+    }
+}
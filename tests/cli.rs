@@ -0,0 +1,76 @@
+use std::{env, fs, process::Command, str::FromStr};
+
+use pngme::{chunk::Chunk, chunk_type::ChunkType, png::Png};
+
+fn write_png(name: &str) -> String {
+    let chunks = ["IHDR", "IDAT", "IEND"]
+        .iter()
+        .map(|chunk_type| Chunk::new(ChunkType::from_str(chunk_type).unwrap(), vec![]))
+        .collect();
+    let path = env::temp_dir().join(format!("pngme_cli_{}_{}", std::process::id(), name));
+    fs::write(&path, Png::from_chunks(chunks).as_bytes()).unwrap();
+
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn test_timings_go_to_stderr() {
+    let path = write_png("timings.png");
+    let output = Command::new(env!("CARGO_BIN_EXE_pngme"))
+        .args(["--timings", "encode", &path, "ruSt", "hey"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    for step in ["parse", "mutation", "write"] {
+        assert!(stderr.contains(&format!("timing: {} took", step)));
+    }
+    assert!(output.stdout.is_empty());
+
+    // 写到标准输出时，PNG 数据里不能混进 timing 行
+    let output = Command::new(env!("CARGO_BIN_EXE_pngme"))
+        .args(["--timings", "encode", &path, "ruSt", "hey", "-"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("timing: write took"));
+    assert!(Png::try_from(output.stdout.as_slice()).is_ok());
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_timings_for_every_writing_command() {
+    let path = write_png("timings_all.png");
+    let other = write_png("timings_other.png");
+    let cases: [(&[&str], &[&str]); 6] = [
+        (&["normalize", &path], &["parse", "mutation", "write"]),
+        (&["dedup", &path], &["parse", "mutation", "write"]),
+        (&["minify", &path], &["parse", "mutation", "write"]),
+        (&["merge", &path, &other], &["parse", "mutation", "write"]),
+        (&["fix", &path], &["parse", "crc recompute", "write"]),
+        (&["remove", &path, "IDAT"], &["parse", "mutation", "write"]),
+    ];
+
+    for (args, steps) in cases {
+        let output = Command::new(env!("CARGO_BIN_EXE_pngme"))
+            .arg("--timings")
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", args);
+
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        let reported: Vec<_> = stderr
+            .lines()
+            .filter_map(|line| line.strip_prefix("timing: "))
+            .filter_map(|line| line.rsplit_once(" took"))
+            .map(|(step, _)| step)
+            .collect();
+        assert_eq!(reported, steps, "{:?}", args);
+    }
+
+    fs::remove_file(path).unwrap();
+    fs::remove_file(other).unwrap();
+}